@@ -1,20 +1,33 @@
+use std::collections::{HashMap, VecDeque};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
 
+use anyhow::Context;
 use async_trait::async_trait;
 use dashmap::DashSet;
 use globset::{Glob, GlobSetBuilder};
-use hyper::{Client, Method, Request};
-use hyper_tls::HttpsConnector;
+use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::{fs, process::Command};
-use toml_edit::{value, Document};
+use toml_edit::{value, Document, Item};
 
 use mol_core::prelude::*;
 
+mod lockfile;
+mod manifest;
+mod registry;
+
+/// Lines of stderr repeated in the error of a failed command
+const STDERR_TAIL_LINES: usize = 5;
+
 #[derive(Clone)]
-pub struct CrateMetadata {}
+pub struct CrateMetadata {
+  /// Root manifest of the workspace, inherited package fields and dependencies resolve from it
+  workspace: Option<PathBuf>,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct CratesError {
@@ -45,24 +58,96 @@ enum CratesResult<T, E = CratesError> {
 pub struct Cargo;
 
 impl Cargo {
+  /// Runs a cargo command in the crate directory, stderr is forwarded and its last lines are kept
+  /// for the error when cargo fails
   async fn run_command<T: AsRef<Path> + Send + Sync>(
     &self,
     command: &str,
     crate_path: T,
     args: Vec<&str>,
-  ) -> anyhow::Result<()> {
-    if let Ok(canon_path) = dunce::canonicalize(crate_path) {
-      Command::new("cargo")
-        .current_dir(canon_path)
-        .arg(command)
-        .args(args)
-        .spawn()
-        .expect("Cargo command failed to start")
-        .wait()
-        .await?;
+  ) -> Result<(), CommandError> {
+    let command_line = ["cargo", command]
+      .iter()
+      .chain(&args)
+      .copied()
+      .collect::<Vec<&str>>()
+      .join(" ");
+
+    let cwd =
+      dunce::canonicalize(&crate_path).map_err(|source| CommandError::WorkingDirectory {
+        command: command_line.clone(),
+        cwd: crate_path.as_ref().to_path_buf(),
+        source,
+      })?;
+
+    let spawn_error = |source| CommandError::Spawn {
+      command: command_line.clone(),
+      cwd: cwd.clone(),
+      source,
+    };
+
+    let mut child = Command::new("cargo")
+      .current_dir(&cwd)
+      .arg(command)
+      .args(args)
+      .stderr(Stdio::piped())
+      .spawn()
+      .map_err(spawn_error)?;
+
+    let mut stderr = VecDeque::with_capacity(STDERR_TAIL_LINES);
+    let mut read_error = None;
+
+    if let Some(child_stderr) = child.stderr.take() {
+      let mut reader = BufReader::new(child_stderr);
+      let mut buffer = Vec::new();
+
+      loop {
+        buffer.clear();
+
+        match reader.read_until(b'\n', &mut buffer).await {
+          Ok(0) => break,
+          Ok(_) => {
+            // cargo forwards the output of build scripts which is not always valid utf-8
+            let line = String::from_utf8_lossy(&buffer)
+              .trim_end_matches(&['\r', '\n'][..])
+              .to_owned();
+
+            eprintln!("{}", line);
+
+            if stderr.len() == STDERR_TAIL_LINES {
+              stderr.pop_front();
+            }
+            stderr.push_back(line);
+          }
+          Err(err) => {
+            read_error = Some(err);
+            break;
+          }
+        }
+      }
     }
 
-    Ok(())
+    // the child is always awaited, even when its stderr could not be read
+    let status = child.wait().await.map_err(spawn_error)?;
+
+    if let Some(source) = read_error {
+      return Err(CommandError::Stderr {
+        command: command_line,
+        cwd,
+        source,
+      });
+    }
+
+    if status.success() {
+      Ok(())
+    } else {
+      Err(CommandError::Failed {
+        command: command_line,
+        cwd,
+        code: status.code(),
+        stderr: Vec::from(stderr).join("\n"),
+      })
+    }
   }
 
   async fn load_document<T: AsRef<Path>>(crate_path: T) -> anyhow::Result<(PathBuf, Document)> {
@@ -73,6 +158,42 @@ impl Cargo {
 
     Ok((crate_path.as_ref().to_path_buf(), document))
   }
+
+  /// `target/mol-publish/<crate directory>` in the workspace root
+  fn publish_target_dir<T: AsRef<Path>>(
+    crate_path: T,
+    metadata: &CrateMetadata,
+  ) -> anyhow::Result<PathBuf> {
+    let crate_dir = dunce::canonicalize(&crate_path)?;
+    let root_dir = match metadata.workspace.as_deref().and_then(Path::parent) {
+      Some(workspace_dir) => dunce::canonicalize(workspace_dir)?,
+      None => crate_dir.clone(),
+    };
+
+    Ok(
+      root_dir
+        .join("target")
+        .join("mol-publish")
+        .join(crate_dir.file_name().unwrap_or_default()),
+    )
+  }
+
+  /// Loads the workspace root manifest unless it is the manifest at `crate_path` itself
+  async fn load_workspace<T: AsRef<Path>>(
+    crate_path: T,
+    metadata: &CrateMetadata,
+  ) -> anyhow::Result<Option<(PathBuf, Document)>> {
+    let workspace = match &metadata.workspace {
+      Some(workspace) => workspace,
+      None => return Ok(None),
+    };
+
+    if dunce::canonicalize(workspace)? == dunce::canonicalize(&crate_path)? {
+      return Ok(None);
+    }
+
+    Ok(Some(Self::load_document(workspace).await?))
+  }
 }
 
 #[async_trait]
@@ -84,24 +205,20 @@ impl PackageManager for Cargo {
   }
 
   async fn load_metadata<T: AsRef<Path> + Send + Sync>(
-    _crate_path: T,
+    crate_path: T,
   ) -> anyhow::Result<Self::Metadata> {
-    Ok(CrateMetadata {})
+    let (crate_path, document) = Self::load_document(crate_path).await?;
+
+    Ok(CrateMetadata {
+      workspace: document.contains_key("workspace").then_some(crate_path),
+    })
   }
 
   async fn validate_package<T: AsRef<Path> + Send + Sync>(
     crate_path: T,
     _: &Self::Metadata,
   ) -> anyhow::Result<()> {
-    let (_, document) = Self::load_document(crate_path).await?;
-
-    if document.contains_key("workspace") {
-      if let Some(workspace) = document["workspace"].as_table() {
-        if workspace.contains_key("package") || workspace.contains_key("dependencies") {
-          println!("Warn: workspace-inheritance isn't fully supported");
-        }
-      }
-    }
+    Self::load_document(crate_path).await?;
 
     Ok(())
   }
@@ -113,38 +230,22 @@ impl PackageManager for Cargo {
     let mut result = Vec::new();
     let (crate_path, document) = Self::load_document(crate_path).await?;
 
-    let (package_name, version) = if document.contains_key("package") {
-      (
-        document["package"]["name"].as_str(),
-        document["package"]["version"].as_str(),
-      )
+    let workspace_document = if document.contains_key("workspace") {
+      None
     } else {
-      (None, None)
+      Self::load_workspace(&crate_path, metadata).await?
+    };
+    let workspace = match &workspace_document {
+      Some((_, workspace)) => Some(workspace),
+      None => Some(&document),
     };
 
-    let mut dependencies = Vec::new();
-
-    if document.contains_key("dependencies") {
-      if let Some(deps) = document["dependencies"].as_table() {
-        for (key, value) in deps.iter() {
-          if value.is_str() {
-            dependencies.push((
-              key.to_owned(),
-              value.as_str().unwrap_or_default().to_owned(),
-            ));
-          } else if value.is_table() || value.is_inline_table() {
-            if let Some(value) = value.as_table() {
-              if value.contains_key("version") {
-                dependencies.push((
-                  key.to_owned(),
-                  value["version"].as_str().unwrap_or_default().to_owned(),
-                ));
-              }
-            }
-          }
-        }
-      }
-    }
+    let package_name = manifest::package_field(&document, workspace, "name").and_then(Item::as_str);
+    let version = manifest::package_field(&document, workspace, "version").and_then(Item::as_str);
+
+    let publish = manifest::publish(&document, workspace);
+
+    let dependencies = manifest::dependencies(&document, workspace);
 
     if let (Some(package_name), Some(version)) = (package_name, version) {
       result.push(Package {
@@ -152,6 +253,7 @@ impl PackageManager for Cargo {
         name: package_name.to_owned(),
         version: version.into(),
         dependencies,
+        publish,
       });
     }
 
@@ -190,45 +292,92 @@ impl PackageManager for Cargo {
     Ok(result)
   }
 
+  /// Members inheriting `version.workspace = true` all write `[workspace.package] version`
+  async fn shared_version_groups<V: Versioned + Send + Sync + 'static>(
+    packages: &[Package<V>],
+    _: &Self::Metadata,
+  ) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut group = Vec::new();
+
+    for package in packages {
+      let (_, document) = Self::load_document(&package.path).await?;
+
+      if manifest::inherits_package_field(&document, "version") {
+        group.push(package.name.clone());
+      }
+    }
+
+    Ok(if group.len() > 1 { vec![group] } else { vec![] })
+  }
+
   async fn check_version<V: Versioned + Send + Sync + 'static>(
     &self,
     package: &Package<V>,
+    registry: &Registry,
     _: &Self::Metadata,
-  ) -> anyhow::Result<bool> {
-    let https = HttpsConnector::new();
-    let client = Client::builder().build::<_, hyper::Body>(https);
-
-    let request = Request::builder()
-      .method(Method::GET)
-      .uri(format!(
-        "https://crates.io/api/v1/crates/{}/{}",
-        package.name, package.version.value
-      ))
-      .header(
-        hyper::header::USER_AGENT,
-        format!(
-          "{}/{} (https://github.com/DmitryDodzin/mol)",
-          env!("CARGO_PKG_NAME"),
-          env!("CARGO_PKG_VERSION")
-        ),
-      )
-      .body(hyper::Body::empty())?;
-
-    let response = client.request(request).await?;
+  ) -> anyhow::Result<VersionCheck> {
+    let crate_dir = package.path.parent().unwrap_or_else(|| Path::new("."));
 
-    let bytes = hyper::body::to_bytes(response.into_body()).await?;
+    if let Some(index) = registry::sparse_index_url(crate_dir, registry).await? {
+      let entry =
+        registry::sparse_index_entry(&index, &package.name, &package.version.value).await?;
 
-    let crates_result = serde_json::from_slice::<CratesResult<CratesVersion>>(&bytes)?;
+      return Ok(match entry {
+        Ok(entry) => {
+          if entry.yanked {
+            println!("Warn: {}@{} is yanked", entry.name, entry.vers);
+          }
 
-    match crates_result {
-      CratesResult::Ok(val) => Ok(val.version.name == package.name),
-      CratesResult::Err { errors } => {
-        for error in errors {
-          println!("crates-error:\n{}", error.detail);
+          VersionCheck::Published
         }
-        Ok(false)
-      }
+        Err(response) => VersionCheck::Missing(response),
+      });
+    }
+
+    let api = registry::api_url(crate_dir, registry).await?;
+    let uri = format!(
+      "{}/api/v1/crates/{}/{}",
+      api, package.name, package.version.value
+    );
+
+    let (status, bytes) = registry::http_get(&uri).await?;
+
+    if status == StatusCode::NOT_FOUND {
+      return Ok(VersionCheck::Missing(format!(
+        "{} from {}: {}",
+        status,
+        uri,
+        registry::response_excerpt(&bytes)
+      )));
     }
+
+    let crates_result = serde_json::from_slice::<CratesResult<CratesVersion>>(&bytes)
+      .with_context(|| {
+        format!(
+          "Invalid response {} from {}: {}",
+          status,
+          uri,
+          registry::response_excerpt(&bytes)
+        )
+      })?;
+
+    Ok(match crates_result {
+      CratesResult::Ok(val) if val.version.name == package.name => VersionCheck::Published,
+      CratesResult::Ok(val) => VersionCheck::Missing(format!(
+        "{} from {} for {}@{}",
+        status, uri, val.version.name, val.version.num
+      )),
+      CratesResult::Err { errors } => VersionCheck::Missing(format!(
+        "{} from {}: {}",
+        status,
+        uri,
+        errors
+          .into_iter()
+          .map(|error| error.detail)
+          .collect::<Vec<String>>()
+          .join(", ")
+      )),
+    })
   }
 
   async fn run_build<T: AsRef<Path> + Send + Sync>(
@@ -243,40 +392,88 @@ impl PackageManager for Cargo {
         crate_path,
         build_args.iter().map(Deref::deref).collect(),
       )
-      .await
+      .await?;
+
+    Ok(())
+  }
+
+  async fn prepare_publish<T: AsRef<Path> + Send + Sync>(
+    &self,
+    crate_path: T,
+    _: &Self::Metadata,
+  ) -> anyhow::Result<()> {
+    self.run_command("update", crate_path, vec![]).await?;
+
+    Ok(())
   }
 
   async fn run_publish<T: AsRef<Path> + Send + Sync>(
     &self,
     crate_path: T,
     publish_args: Vec<String>,
+    registry: &Registry,
     dry_run: bool,
-    _: &Self::Metadata,
+    concurrent: bool,
+    metadata: &Self::Metadata,
   ) -> anyhow::Result<()> {
-    let args = if dry_run {
-      vec!["--dry-run"]
-        .into_iter()
-        .chain(publish_args.iter().map(Deref::deref))
-        .collect()
+    let mut args = Vec::new();
+
+    if dry_run {
+      args.push("--dry-run");
+    }
+
+    if let Some(name) = &registry.name {
+      args.extend(["--registry", name]);
+    }
+
+    // concurrent publishes would otherwise wait on each other for the lock of the shared target
+    // directory, every crate gets its own under the workspace target directory
+    let target_dir = if concurrent {
+      Some(Self::publish_target_dir(&crate_path, metadata)?)
     } else {
-      publish_args.iter().map(Deref::deref).collect()
+      None
     };
 
-    self.run_command("update", &crate_path, vec![]).await?;
+    if let Some(target_dir) = &target_dir {
+      args.push("--target-dir");
+      args.push(
+        target_dir
+          .to_str()
+          .context("Invalid target directory path")?,
+      );
+    }
+
+    args.extend(publish_args.iter().map(Deref::deref));
 
-    self.run_command("publish", crate_path, args).await
+    self.run_command("publish", crate_path, args).await?;
+
+    Ok(())
   }
 
   async fn apply_version<T: AsRef<Path> + Send + Sync>(
     &self,
     crate_path: T,
     version: &str,
-    _: &Self::Metadata,
+    metadata: &Self::Metadata,
   ) -> anyhow::Result<()> {
     let (crate_path, mut document) = Self::load_document(crate_path).await?;
 
-    if document.contains_key("package") {
+    if !document.contains_key("package") {
+      return Ok(());
+    }
+
+    if !manifest::inherits_package_field(&document, "version") {
       document["package"]["version"] = value(version);
+    } else if document.contains_key("workspace") {
+      manifest::set_workspace_package_field(&mut document, "version", version);
+    } else if let Some((workspace_path, mut workspace)) =
+      Self::load_workspace(&crate_path, metadata).await?
+    {
+      manifest::set_workspace_package_field(&mut workspace, "version", version);
+
+      fs::write(&workspace_path, workspace.to_string()).await?;
+
+      return Ok(());
     }
 
     fs::write(&crate_path, document.to_string()).await?;
@@ -287,24 +484,361 @@ impl PackageManager for Cargo {
   async fn apply_dependency_version<T: AsRef<Path> + Send + Sync>(
     &self,
     crate_path: T,
-    name: &str,
+    dependency: &Dependency,
     version: &str,
-    _: &Self::Metadata,
+    metadata: &Self::Metadata,
   ) -> anyhow::Result<()> {
     let (crate_path, mut document) = Self::load_document(crate_path).await?;
 
-    if document.contains_key("dependencies") {
-      let dep = &document["dependencies"][name];
-
-      if dep.is_inline_table() {
-        document["dependencies"][name]["version"] = value(version);
-      } else if dep.is_str() {
-        document["dependencies"][name] = value(version);
+    let mut written = manifest::set_dependency_version(&mut document, dependency, version);
+
+    if manifest::inherits_dependency(&document, dependency) {
+      if document.contains_key("workspace") {
+        written |= manifest::set_workspace_dependency_version(&mut document, dependency, version);
+      } else if let Some((workspace_path, mut workspace)) =
+        Self::load_workspace(&crate_path, metadata).await?
+      {
+        if manifest::set_workspace_dependency_version(&mut workspace, dependency, version) {
+          fs::write(&workspace_path, workspace.to_string()).await?;
+          written = true;
+        }
       }
     }
 
+    if !written {
+      anyhow::bail!(
+        "Could not find dependency {} = \"{}\" in {}",
+        dependency.key(),
+        dependency.version,
+        crate_path.display()
+      );
+    }
+
     fs::write(&crate_path, document.to_string()).await?;
 
     Ok(())
   }
+
+  async fn update_lockfile<T: AsRef<Path> + Send + Sync>(
+    &self,
+    crate_path: T,
+    versions: &HashMap<&str, String>,
+    metadata: &Self::Metadata,
+  ) -> anyhow::Result<()> {
+    let manifest_path = metadata
+      .workspace
+      .as_deref()
+      .unwrap_or_else(|| crate_path.as_ref());
+    let lockfile_path = manifest_path
+      .parent()
+      .unwrap_or_else(|| Path::new("."))
+      .join("Cargo.lock");
+
+    if !lockfile_path.exists() {
+      return Ok(());
+    }
+
+    let mut document = fs::read_to_string(&lockfile_path)
+      .await?
+      .parse::<Document>()
+      .with_context(|| format!("Invalid lockfile at {:?}", lockfile_path))?;
+
+    if lockfile::set_versions(&mut document, versions) {
+      fs::write(&lockfile_path, document.to_string()).await?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  /// Writes the files into a fresh directory under the system temp directory
+  async fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("mol-cargo-{}-{}", name, std::process::id()));
+
+    if root.exists() {
+      fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    for (path, content) in files {
+      let path = root.join(path);
+
+      fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+      fs::write(path, content).await.unwrap();
+    }
+
+    root
+  }
+
+  #[tokio::test]
+  async fn run_command_failed() {
+    let root = fixture("run-command-failed", &[("README.md", "")]).await;
+
+    match Cargo.run_command("locate-project", &root, vec![]).await {
+      Err(CommandError::Failed {
+        command, stderr, ..
+      }) => {
+        assert_eq!(command, "cargo locate-project");
+        assert!(stderr.contains("could not find `Cargo.toml`"));
+        assert!(stderr.lines().count() <= STDERR_TAIL_LINES);
+      }
+      result => panic!("unexpected result {:?}", result),
+    }
+
+    fs::remove_dir_all(root).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn publish_target_dir() {
+    let root = fixture(
+      "publish-target-dir",
+      &[
+        ("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+        (
+          "crates/foo/Cargo.toml",
+          "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+        ),
+      ],
+    )
+    .await;
+    let metadata = Cargo::load_metadata(root.join("Cargo.toml")).await.unwrap();
+    let root_dir = dunce::canonicalize(&root).unwrap();
+
+    assert_eq!(
+      Cargo::publish_target_dir(root.join("crates").join("foo"), &metadata).unwrap(),
+      root_dir.join("target").join("mol-publish").join("foo")
+    );
+    assert_eq!(
+      Cargo::publish_target_dir(
+        root.join("crates").join("foo"),
+        &CrateMetadata { workspace: None }
+      )
+      .unwrap(),
+      root_dir
+        .join("crates")
+        .join("foo")
+        .join("target")
+        .join("mol-publish")
+        .join("foo")
+    );
+
+    fs::remove_dir_all(root).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn seek_packages_publish() {
+    let root = fixture(
+      "seek-packages-publish",
+      &[
+        ("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+        (
+          "crates/private/Cargo.toml",
+          "[package]\nname = \"private\"\nversion = \"0.1.0\"\npublish = false\n",
+        ),
+        (
+          "crates/nowhere/Cargo.toml",
+          "[package]\nname = \"nowhere\"\nversion = \"0.1.0\"\npublish = []\n",
+        ),
+        (
+          "crates/public/Cargo.toml",
+          "[package]\nname = \"public\"\nversion = \"0.1.0\"\npublish = [\"crates-io\"]\n",
+        ),
+      ],
+    )
+    .await;
+    let metadata = Cargo::load_metadata(root.join("Cargo.toml")).await.unwrap();
+    let mut packages = Vec::new();
+
+    for member in ["private", "nowhere", "public"] {
+      let manifest_path = root.join("crates").join(member).join("Cargo.toml");
+
+      packages.extend(
+        Cargo::seek_packages::<_, Semantic>(&manifest_path, &metadata)
+          .await
+          .unwrap()
+          .into_iter()
+          .map(|package| (package.name, package.publish)),
+      );
+    }
+
+    packages.sort();
+
+    assert_eq!(
+      packages,
+      vec![
+        ("nowhere".to_owned(), false),
+        ("private".to_owned(), false),
+        ("public".to_owned(), true),
+      ]
+    );
+
+    fs::remove_dir_all(root).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn shared_version_groups() {
+    let root = fixture(
+      "shared-version-groups",
+      &[
+        (
+          "Cargo.toml",
+          "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.package]\nversion = \"0.3.0\"\n",
+        ),
+        (
+          "crates/foo/Cargo.toml",
+          "[package]\nname = \"foo\"\nversion.workspace = true\n",
+        ),
+        (
+          "crates/bar/Cargo.toml",
+          "[package]\nname = \"bar\"\nversion = { workspace = true }\n",
+        ),
+        (
+          "crates/baz/Cargo.toml",
+          "[package]\nname = \"baz\"\nversion = \"1.0.0\"\n",
+        ),
+      ],
+    )
+    .await;
+    let metadata = Cargo::load_metadata(root.join("Cargo.toml")).await.unwrap();
+    let mut packages = Vec::new();
+
+    for member in ["foo", "bar", "baz"] {
+      packages.extend(
+        Cargo::seek_packages::<_, Semantic>(
+          root.join("crates").join(member).join("Cargo.toml"),
+          &metadata,
+        )
+        .await
+        .unwrap(),
+      );
+    }
+
+    let groups = Cargo::shared_version_groups(&packages, &metadata)
+      .await
+      .unwrap();
+
+    assert_eq!(groups, vec![vec!["foo".to_owned(), "bar".to_owned()]]);
+
+    let graph = packages.as_package_graph();
+    let mut bump = Bump::default().with_fixed(groups);
+
+    for (name, version) in [("foo", Semantic::patch()), ("bar", Semantic::minor())] {
+      bump.add(
+        Changeset {
+          packages: [(name.to_owned(), VersionMod::new(version))].into(),
+          message: "Hi".to_owned(),
+          ..Default::default()
+        },
+        &graph,
+      );
+    }
+
+    let plan = ReleasePlan::new(&graph, &bump).unwrap();
+
+    assert_eq!(plan.package("foo").unwrap().next_version, "0.4.0");
+    assert_eq!(plan.package("bar").unwrap().next_version, "0.4.0");
+    assert!(plan.package("baz").is_none());
+
+    fs::remove_dir_all(root).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn dev_dependency_cycle() {
+    let root = fixture(
+      "dev-dependency-cycle",
+      &[
+        (
+          "foo/Cargo.toml",
+          "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n\n[dev-dependencies]\nbar = { path = \"../bar\", version = \"0.1\" }\n",
+        ),
+        (
+          "bar/Cargo.toml",
+          "[package]\nname = \"bar\"\nversion = \"0.1.0\"\n\n[dependencies]\nfoo = { path = \"../foo\", version = \"0.1\" }\n",
+        ),
+      ],
+    )
+    .await;
+    let mut packages = Vec::new();
+
+    for member in ["foo", "bar"] {
+      let manifest_path = root.join(member).join("Cargo.toml");
+      let metadata = Cargo::load_metadata(&manifest_path).await.unwrap();
+
+      packages.extend(
+        Cargo::seek_packages::<_, Semantic>(&manifest_path, &metadata)
+          .await
+          .unwrap(),
+      );
+    }
+
+    assert!(packages
+      .as_package_graph()
+      .with_ignore_dev_cycles(false)
+      .update_order()
+      .is_err());
+    assert_eq!(
+      packages
+        .as_package_graph()
+        .update_order()
+        .unwrap()
+        .into_iter()
+        .map(|package| package.name.as_str())
+        .collect::<Vec<&str>>(),
+      vec!["foo", "bar"]
+    );
+
+    fs::remove_dir_all(root).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn apply_dependency_version() {
+    let root = fixture(
+      "apply-dependency-version",
+      &[(
+        "Cargo.toml",
+        "[package]\nname = \"mol\"\nversion = \"0.3.0\"\n\n[dependencies]\nmol-core = \"0.3\"\n",
+      )],
+    )
+    .await;
+    let manifest_path = root.join("Cargo.toml");
+    let metadata = Cargo::load_metadata(&manifest_path).await.unwrap();
+
+    Cargo
+      .apply_dependency_version(
+        &manifest_path,
+        &Dependency::new("mol-core", "0.3"),
+        "0.4",
+        &metadata,
+      )
+      .await
+      .unwrap();
+
+    let error = Cargo
+      .apply_dependency_version(
+        &manifest_path,
+        &Dependency::new("mol-core", "0.2"),
+        "0.5",
+        &metadata,
+      )
+      .await
+      .unwrap_err();
+
+    assert_eq!(
+      error.to_string(),
+      format!(
+        "Could not find dependency mol-core = \"0.2\" in {}",
+        manifest_path.display()
+      )
+    );
+    assert!(fs::read_to_string(&manifest_path)
+      .await
+      .unwrap()
+      .contains("mol-core = \"0.4\""));
+
+    fs::remove_dir_all(root).await.unwrap();
+  }
 }
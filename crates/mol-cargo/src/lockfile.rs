@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use toml_edit::{value, Document, Item, Value};
+
+/// Rewrites the versions of workspace members (packages without a `source`) and the dependency
+/// references pinned to their previous versions, returns false when no member was found
+pub fn set_versions(document: &mut Document, versions: &HashMap<&str, String>) -> bool {
+  let packages = match document
+    .get_mut("package")
+    .and_then(Item::as_array_of_tables_mut)
+  {
+    Some(packages) => packages,
+    None => return false,
+  };
+
+  // name -> (previous version, next version)
+  let mut changed = HashMap::new();
+
+  for package in packages.iter_mut() {
+    if package.contains_key("source") {
+      continue;
+    }
+
+    let name = package.get("name").and_then(Item::as_str);
+    let version = package.get("version").and_then(Item::as_str);
+
+    if let (Some(name), Some(version)) = (name, version) {
+      if let Some(next_version) = versions.get(name) {
+        changed.insert(name.to_owned(), (version.to_owned(), next_version.clone()));
+        package["version"] = value(next_version.as_str());
+      }
+    }
+  }
+
+  // dependencies are referenced as "name", "name version" or "name version (source)", the
+  // version is only there when the lockfile has several versions of the package
+  for package in packages.iter_mut() {
+    let dependencies = package
+      .get_mut("dependencies")
+      .and_then(Item::as_array_mut)
+      .into_iter()
+      .flat_map(|dependencies| dependencies.iter_mut());
+
+    for dependency in dependencies {
+      let next = match dependency.as_str().map(|reference| {
+        reference
+          .split(' ')
+          .map(str::to_owned)
+          .collect::<Vec<String>>()
+      }) {
+        Some(reference) if reference.len() == 2 => match changed.get(&reference[0]) {
+          Some((version, next_version)) if *version == reference[1] => {
+            format!("{} {}", reference[0], next_version)
+          }
+          _ => continue,
+        },
+        _ => continue,
+      };
+
+      let decor = dependency.decor().clone();
+      *dependency = Value::from(next);
+      *dependency.decor_mut() = decor;
+    }
+  }
+
+  !changed.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "mol"
+version = "0.3.0"
+dependencies = [
+ "mol-core 0.3.0",
+ "mol-core 0.2.0 (registry+https://github.com/rust-lang/crates.io-index)",
+ "serde",
+]
+
+[[package]]
+name = "mol-core"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "mol-core"
+version = "0.3.0"
+dependencies = [
+ "serde",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+  #[test]
+  fn set_versions() {
+    let mut document = LOCKFILE.parse::<Document>().unwrap();
+
+    let versions = HashMap::from([
+      ("mol", "0.3.1".to_owned()),
+      ("mol-core", "0.4.0".to_owned()),
+    ]);
+
+    assert!(super::set_versions(&mut document, &versions));
+
+    assert_eq!(
+      document.to_string(),
+      LOCKFILE
+        .replace(
+          "name = \"mol\"\nversion = \"0.3.0\"",
+          "name = \"mol\"\nversion = \"0.3.1\""
+        )
+        .replace("\"mol-core 0.3.0\"", "\"mol-core 0.4.0\"")
+        .replace(
+          "name = \"mol-core\"\nversion = \"0.3.0\"",
+          "name = \"mol-core\"\nversion = \"0.4.0\""
+        )
+    );
+
+    assert!(!super::set_versions(
+      &mut document,
+      &HashMap::from([("serde", "1.0.1".to_owned())])
+    ));
+  }
+}
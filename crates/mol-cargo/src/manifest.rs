@@ -0,0 +1,453 @@
+use toml_edit::{value, Document, Item, TableLike};
+
+use mol_core::prelude::*;
+
+const DEPENDENCY_TABLES: [(&str, DependencyKind); 5] = [
+  ("dependencies", DependencyKind::Normal),
+  ("build-dependencies", DependencyKind::Build),
+  ("build_dependencies", DependencyKind::Build),
+  ("dev-dependencies", DependencyKind::Dev),
+  ("dev_dependencies", DependencyKind::Dev),
+];
+
+fn table_kind(key: &str) -> Option<DependencyKind> {
+  DEPENDENCY_TABLES
+    .iter()
+    .find(|(table, _)| *table == key)
+    .map(|(_, kind)| *kind)
+}
+
+/// `{ workspace = true }` entries inherit their value from the workspace root manifest
+fn is_inherited(item: &Item) -> bool {
+  item
+    .as_table_like()
+    .and_then(|table| table.get("workspace"))
+    .and_then(Item::as_bool)
+    .unwrap_or(false)
+}
+
+fn workspace_dependency<'a>(workspace: &'a Document, key: &str) -> Option<&'a Item> {
+  workspace.get("workspace")?.get("dependencies")?.get(key)
+}
+
+/// Field of the `[package]` table, inherited fields are resolved from `[workspace.package]`
+pub fn package_field<'a>(
+  document: &'a Document,
+  workspace: Option<&'a Document>,
+  field: &str,
+) -> Option<&'a Item> {
+  let item = document.get("package")?.get(field)?;
+
+  if is_inherited(item) {
+    workspace?.get("workspace")?.get("package")?.get(field)
+  } else {
+    Some(item)
+  }
+}
+
+/// False for `publish = false` and `publish = []`, both mark a crate that must never be published
+pub fn publish(document: &Document, workspace: Option<&Document>) -> bool {
+  match package_field(document, workspace, "publish") {
+    Some(publish) if publish.is_array() => publish
+      .as_array()
+      .map(|registries| !registries.is_empty())
+      .unwrap_or(true),
+    Some(publish) => publish.as_bool().unwrap_or(true),
+    None => true,
+  }
+}
+
+pub fn inherits_package_field(document: &Document, field: &str) -> bool {
+  document
+    .get("package")
+    .and_then(|package| package.get(field))
+    .map(is_inherited)
+    .unwrap_or(false)
+}
+
+pub fn set_workspace_package_field(workspace: &mut Document, field: &str, version: &str) {
+  workspace["workspace"]["package"][field] = value(version);
+}
+
+/// Version requirement of a dependency entry, None for dependencies without a version ie path only
+fn entry_version(entry: &Item) -> Option<&str> {
+  match entry.as_table_like() {
+    Some(table) => table.get("version").and_then(Item::as_str),
+    None => entry.as_str(),
+  }
+}
+
+/// Every dependency table of the manifest including the `[target.'cfg(..)'.*]` ones
+fn dependency_tables(document: &Document) -> Vec<(&dyn TableLike, DependencyKind)> {
+  let root: &dyn TableLike = document.as_table();
+
+  let targets = root
+    .get("target")
+    .and_then(Item::as_table_like)
+    .into_iter()
+    .flat_map(|targets| targets.iter())
+    .filter_map(|(_, target)| target.as_table_like());
+
+  Some(root)
+    .into_iter()
+    .chain(targets)
+    .flat_map(|table| table.iter())
+    .filter_map(|(key, item)| Some((item.as_table_like()?, table_kind(key)?)))
+    .collect()
+}
+
+fn dependency_tables_mut(document: &mut Document) -> Vec<(&mut dyn TableLike, DependencyKind)> {
+  let mut tables = Vec::new();
+
+  for (key, item) in document.as_table_mut().iter_mut() {
+    if key.get() == "target" {
+      let targets = item
+        .as_table_like_mut()
+        .into_iter()
+        .flat_map(|targets| targets.iter_mut());
+
+      for (_, target) in targets {
+        let target_tables = target
+          .as_table_like_mut()
+          .into_iter()
+          .flat_map(|target| target.iter_mut());
+
+        for (key, item) in target_tables {
+          if let (Some(kind), Some(table)) = (table_kind(key.get()), item.as_table_like_mut()) {
+            tables.push((table, kind));
+          }
+        }
+      }
+    } else if let (Some(kind), Some(table)) = (table_kind(key.get()), item.as_table_like_mut()) {
+      tables.push((table, kind));
+    }
+  }
+
+  tables
+}
+
+/// Dependency declared under `key`, renamed dependencies (`package = "..."`) resolve to the real
+/// package name and keep the key as the alias, inherited dependencies resolve through
+/// `[workspace.dependencies]`
+fn entry_dependency(
+  key: &str,
+  entry: &Item,
+  kind: DependencyKind,
+  workspace: Option<&Document>,
+) -> Option<Dependency> {
+  let entry = if is_inherited(entry) {
+    workspace_dependency(workspace?, key)?
+  } else {
+    entry
+  };
+
+  let version = entry_version(entry)?;
+
+  let package = entry
+    .as_table_like()
+    .and_then(|table| table.get("package"))
+    .and_then(Item::as_str);
+
+  Some(match package {
+    Some(package) if package != key => Dependency::new(package, version)
+      .with_kind(kind)
+      .with_alias(key),
+    _ => Dependency::new(key, version).with_kind(kind),
+  })
+}
+
+/// Dependencies with a version requirement from every dependency table of the manifest
+pub fn dependencies(document: &Document, workspace: Option<&Document>) -> Vec<Dependency> {
+  dependency_tables(document)
+    .into_iter()
+    .flat_map(|(table, kind)| {
+      table
+        .iter()
+        .filter_map(move |(key, entry)| entry_dependency(key, entry, kind, workspace))
+    })
+    .collect()
+}
+
+/// True when the dependency is declared with `{ workspace = true }` in a table of its kind
+pub fn inherits_dependency(document: &Document, dependency: &Dependency) -> bool {
+  dependency_tables(document)
+    .into_iter()
+    .filter(|(_, kind)| *kind == dependency.kind)
+    .filter_map(|(table, _)| table.get(dependency.key()))
+    .any(is_inherited)
+}
+
+/// Rewrites the requirement of the entry, an entry already at `version` counts as written since a
+/// workspace entry is shared by every member inheriting it
+fn set_entry_version(entry: &mut Item, dependency: &Dependency, version: &str) -> bool {
+  let current = entry_version(entry);
+
+  if current == Some(version) {
+    return true;
+  }
+
+  if current != Some(dependency.version.as_str()) {
+    return false;
+  }
+
+  match entry.as_table_like_mut() {
+    Some(entry) => {
+      entry.insert("version", value(version));
+    }
+    None => *entry = value(version),
+  }
+
+  true
+}
+
+/// Rewrites the requirement of the dependency in `[workspace.dependencies]`
+pub fn set_workspace_dependency_version(
+  workspace: &mut Document,
+  dependency: &Dependency,
+  version: &str,
+) -> bool {
+  workspace
+    .get_mut("workspace")
+    .and_then(|workspace| workspace.get_mut("dependencies"))
+    .and_then(|dependencies| dependencies.get_mut(dependency.key()))
+    .map(|entry| set_entry_version(entry, dependency, version))
+    .unwrap_or(false)
+}
+
+/// Rewrites the requirement of the dependency in every table of its kind where it has the same
+/// requirement, returns false when no entry was found
+pub fn set_dependency_version(
+  document: &mut Document,
+  dependency: &Dependency,
+  version: &str,
+) -> bool {
+  let mut found = false;
+
+  for (table, kind) in dependency_tables_mut(document) {
+    if kind != dependency.kind {
+      continue;
+    }
+
+    if let Some(entry) = table.get_mut(dependency.key()) {
+      found |= set_entry_version(entry, dependency, version);
+    }
+  }
+
+  found
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  const MANIFEST: &str = r#"
+[package]
+name = "mol"
+version = "0.3.0"
+
+[dependencies]
+mol-core = { path = "crates/mol-core", version = "0.3" }
+serde = "1"
+
+[dev-dependencies]
+mol-core = { path = "crates/mol-core", version = "0.3", features = ["testing"] }
+core = { package = "mol-core", path = "crates/mol-core", version = "0.3" }
+
+[build-dependencies.mol-build]
+path = "crates/mol-build"
+version = "0.3"
+
+[target.'cfg(unix)'.dependencies]
+mol-unix = "0.3"
+
+[target.'cfg(windows)'.dependencies]
+mol-windows = { path = "crates/mol-windows" }
+"#;
+
+  #[test]
+  fn dependencies() {
+    let document = MANIFEST.parse::<Document>().unwrap();
+
+    assert_eq!(
+      super::dependencies(&document, None),
+      vec![
+        Dependency::new("mol-core", "0.3"),
+        Dependency::new("serde", "1"),
+        Dependency::new("mol-core", "0.3").with_kind(DependencyKind::Dev),
+        Dependency::new("mol-core", "0.3")
+          .with_kind(DependencyKind::Dev)
+          .with_alias("core"),
+        Dependency::new("mol-build", "0.3").with_kind(DependencyKind::Build),
+        Dependency::new("mol-unix", "0.3"),
+      ]
+    );
+  }
+
+  #[test]
+  fn publish() {
+    let publish = |field: &str| {
+      let document = format!("[package]\nname = \"mol\"\n{}\n", field)
+        .parse::<Document>()
+        .unwrap();
+
+      super::publish(&document, None)
+    };
+
+    assert!(publish(""));
+    assert!(!publish("publish = false"));
+    assert!(publish("publish = true"));
+    assert!(!publish("publish = []"));
+    assert!(publish("publish = [\"crates-io\"]"));
+  }
+
+  const WORKSPACE: &str = r#"
+[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "0.3.0"
+publish = false
+
+[workspace.dependencies]
+mol-core = { path = "crates/mol-core", version = "0.3" }
+core = { package = "mol-core", path = "crates/mol-core", version = "0.3" }
+"#;
+
+  const MEMBER: &str = r#"
+[package]
+name = "mol"
+version.workspace = true
+publish = { workspace = true }
+
+[dependencies]
+mol-core = { workspace = true, features = ["testing"] }
+
+[dev-dependencies]
+core = { workspace = true }
+"#;
+
+  #[test]
+  fn inherited() {
+    let workspace = WORKSPACE.parse::<Document>().unwrap();
+    let document = MEMBER.parse::<Document>().unwrap();
+
+    assert_eq!(
+      package_field(&document, Some(&workspace), "version").and_then(Item::as_str),
+      Some("0.3.0")
+    );
+    assert_eq!(
+      package_field(&document, Some(&workspace), "publish").and_then(Item::as_bool),
+      Some(false)
+    );
+    assert!(!super::publish(&document, Some(&workspace)));
+    assert!(inherits_package_field(&document, "version"));
+    assert!(!inherits_package_field(&document, "name"));
+
+    let dependencies = super::dependencies(&document, Some(&workspace));
+
+    assert_eq!(
+      dependencies,
+      vec![
+        Dependency::new("mol-core", "0.3"),
+        Dependency::new("mol-core", "0.3")
+          .with_kind(DependencyKind::Dev)
+          .with_alias("core"),
+      ]
+    );
+    assert!(inherits_dependency(&document, &dependencies[0]));
+    assert!(inherits_dependency(&document, &dependencies[1]));
+  }
+
+  #[test]
+  fn set_workspace_versions() {
+    let mut workspace = WORKSPACE.parse::<Document>().unwrap();
+    let document = MEMBER.parse::<Document>().unwrap();
+
+    set_workspace_package_field(&mut workspace, "version", "0.4.0");
+
+    assert!(set_workspace_dependency_version(
+      &mut workspace,
+      &Dependency::new("mol-core", "0.3")
+        .with_kind(DependencyKind::Dev)
+        .with_alias("core"),
+      "0.4"
+    ));
+    assert!(set_workspace_dependency_version(
+      &mut workspace,
+      &Dependency::new("mol-core", "0.3")
+        .with_kind(DependencyKind::Dev)
+        .with_alias("core"),
+      "0.4"
+    ));
+    assert!(!set_workspace_dependency_version(
+      &mut workspace,
+      &Dependency::new("mol-core", "0.2"),
+      "0.4"
+    ));
+
+    assert_eq!(
+      package_field(&document, Some(&workspace), "version").and_then(Item::as_str),
+      Some("0.4.0")
+    );
+    assert_eq!(
+      super::dependencies(&document, Some(&workspace)),
+      vec![
+        Dependency::new("mol-core", "0.3"),
+        Dependency::new("mol-core", "0.4")
+          .with_kind(DependencyKind::Dev)
+          .with_alias("core"),
+      ]
+    );
+  }
+
+  #[test]
+  fn set_dependency_version() {
+    let mut document = MANIFEST.parse::<Document>().unwrap();
+
+    assert!(super::set_dependency_version(
+      &mut document,
+      &Dependency::new("mol-core", "0.3").with_kind(DependencyKind::Dev),
+      "0.4"
+    ));
+    assert!(super::set_dependency_version(
+      &mut document,
+      &Dependency::new("mol-core", "0.3")
+        .with_kind(DependencyKind::Dev)
+        .with_alias("core"),
+      "0.4.1"
+    ));
+    assert!(super::set_dependency_version(
+      &mut document,
+      &Dependency::new("mol-build", "0.3").with_kind(DependencyKind::Build),
+      "0.4"
+    ));
+    assert!(super::set_dependency_version(
+      &mut document,
+      &Dependency::new("mol-unix", "0.3"),
+      "0.4"
+    ));
+    assert!(!super::set_dependency_version(
+      &mut document,
+      &Dependency::new("mol-windows", "0.3"),
+      "0.4"
+    ));
+
+    assert_eq!(
+      super::dependencies(&document, None),
+      vec![
+        Dependency::new("mol-core", "0.3"),
+        Dependency::new("serde", "1"),
+        Dependency::new("mol-core", "0.4").with_kind(DependencyKind::Dev),
+        Dependency::new("mol-core", "0.4.1")
+          .with_kind(DependencyKind::Dev)
+          .with_alias("core"),
+        Dependency::new("mol-build", "0.4").with_kind(DependencyKind::Build),
+        Dependency::new("mol-unix", "0.4"),
+      ]
+    );
+    assert!(document.to_string().contains(
+      r#"mol-core = { path = "crates/mol-core", version = "0.4", features = ["testing"] }"#
+    ));
+  }
+}
@@ -0,0 +1,420 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use hyper::{body::Bytes, Client, Method, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use serde::Deserialize;
+use tokio::fs;
+use toml_edit::Document;
+
+use mol_core::prelude::*;
+
+pub const CRATES_IO_API: &str = "https://crates.io";
+pub const CRATES_IO_INDEX: &str = "sparse+https://index.crates.io/";
+
+#[derive(Debug, Deserialize)]
+struct IndexConfig {
+  api: Option<String>,
+}
+
+/// Single version line of a sparse index file
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct IndexEntry {
+  pub name: String,
+  pub vers: String,
+  pub yanked: bool,
+}
+
+pub async fn http_get(uri: &str) -> anyhow::Result<(StatusCode, Bytes)> {
+  let https = HttpsConnector::new();
+  let client = Client::builder().build::<_, hyper::Body>(https);
+
+  let request = Request::builder()
+    .method(Method::GET)
+    .uri(uri)
+    .header(
+      hyper::header::USER_AGENT,
+      format!(
+        "{}/{} (https://github.com/DmitryDodzin/mol)",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+      ),
+    )
+    .body(hyper::Body::empty())?;
+
+  let response = client.request(request).await?;
+  let status = response.status();
+
+  Ok((status, hyper::body::to_bytes(response.into_body()).await?))
+}
+
+/// Cargo environment variables use the upper cased registry name with dashes as underscores
+fn env_key(name: &str) -> String {
+  name.to_uppercase().replace('-', "_")
+}
+
+fn registry_index(document: &Document, name: &str) -> Option<String> {
+  document
+    .get("registries")
+    .and_then(|registries| registries.get(name))
+    .and_then(|registry| registry.get("index"))
+    .and_then(|index| index.as_str())
+    .map(str::to_owned)
+}
+
+fn default_registry(document: &Document) -> Option<String> {
+  document
+    .get("registry")
+    .and_then(|registry| registry.get("default"))
+    .and_then(|default| default.as_str())
+    .map(str::to_owned)
+}
+
+/// Cargo config files in the order cargo prefers them, the closest to the crate first and
+/// `$CARGO_HOME/config.toml` last
+fn cargo_config_paths(crate_dir: &Path) -> Vec<PathBuf> {
+  let crate_dir = dunce::canonicalize(crate_dir).unwrap_or_else(|_| crate_dir.to_path_buf());
+
+  let cargo_home = env::var_os("CARGO_HOME")
+    .map(PathBuf::from)
+    .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+
+  crate_dir
+    .ancestors()
+    .map(|dir| dir.join(".cargo"))
+    .chain(cargo_home)
+    .flat_map(|dir| [dir.join("config.toml"), dir.join("config")])
+    .collect()
+}
+
+async fn cargo_config_value<F>(crate_dir: &Path, value: F) -> anyhow::Result<Option<String>>
+where
+  F: Fn(&Document) -> Option<String>,
+{
+  for config_path in cargo_config_paths(crate_dir) {
+    if !config_path.exists() {
+      continue;
+    }
+
+    let document = fs::read_to_string(&config_path)
+      .await?
+      .parse::<Document>()
+      .with_context(|| format!("Invalid cargo config at {:?}", config_path))?;
+
+    if let Some(value) = value(&document) {
+      return Ok(Some(value));
+    }
+  }
+
+  Ok(None)
+}
+
+/// Name of the registry cargo publishes to, None for crates.io
+pub async fn registry_name(
+  crate_dir: &Path,
+  registry: &Registry,
+) -> anyhow::Result<Option<String>> {
+  if let Some(name) = &registry.name {
+    return Ok(Some(name.clone()));
+  }
+
+  if let Ok(name) = env::var("CARGO_REGISTRY_DEFAULT") {
+    return Ok(Some(name));
+  }
+
+  cargo_config_value(crate_dir, default_registry).await
+}
+
+/// Index url of a named registry from `CARGO_REGISTRIES_<NAME>_INDEX` or `.cargo/config.toml`
+pub async fn registry_index_url(crate_dir: &Path, name: &str) -> anyhow::Result<String> {
+  if let Ok(index) = env::var(format!("CARGO_REGISTRIES_{}_INDEX", env_key(name))) {
+    return Ok(index);
+  }
+
+  cargo_config_value(crate_dir, |document| registry_index(document, name))
+    .await?
+    .ok_or_else(|| {
+      anyhow::anyhow!(
+        "Registry \"{}\" is not configured, set CARGO_REGISTRIES_{}_INDEX or add it to .cargo/config.toml",
+        name,
+        env_key(name)
+      )
+    })
+}
+
+/// Base url of the registry api, the mol config takes precedence then the `api` field of a sparse
+/// index `config.json`
+pub async fn api_url(crate_dir: &Path, registry: &Registry) -> anyhow::Result<String> {
+  if let Some(api) = &registry.api {
+    return Ok(api.trim_end_matches('/').to_owned());
+  }
+
+  let name = match registry_name(crate_dir, registry).await? {
+    Some(name) => name,
+    None => return Ok(CRATES_IO_API.to_owned()),
+  };
+
+  let index = registry_index_url(crate_dir, &name).await?;
+
+  let sparse_index = index.strip_prefix("sparse+").ok_or_else(|| {
+    anyhow::anyhow!(
+      "Unable to resolve the api of the git index registry \"{}\", set `api` under [registry] in the mol config",
+      name
+    )
+  })?;
+
+  let (status, bytes) = http_get(&format!(
+    "{}/config.json",
+    sparse_index.trim_end_matches('/')
+  ))
+  .await
+  .with_context(|| format!("Unable to fetch the index config of registry \"{}\"", name))?;
+
+  if !status.is_success() {
+    return Err(anyhow::anyhow!(
+      "Unable to fetch the index config of registry \"{}\", got {}",
+      name,
+      status
+    ));
+  }
+
+  serde_json::from_slice::<IndexConfig>(&bytes)?
+    .api
+    .map(|api| api.trim_end_matches('/').to_owned())
+    .ok_or_else(|| anyhow::anyhow!("Registry \"{}\" does not provide an api", name))
+}
+
+/// Sparse index url of the registry or None when the registry only has a git index (or the api
+/// is explicitly configured without an index)
+pub async fn sparse_index_url(
+  crate_dir: &Path,
+  registry: &Registry,
+) -> anyhow::Result<Option<String>> {
+  let index = match (&registry.index, &registry.api) {
+    (Some(index), _) => index.clone(),
+    (None, Some(_)) => return Ok(None),
+    (None, None) => match registry_name(crate_dir, registry).await? {
+      Some(name) => registry_index_url(crate_dir, &name).await?,
+      None => CRATES_IO_INDEX.to_owned(),
+    },
+  };
+
+  Ok(
+    index
+      .strip_prefix("sparse+")
+      .map(|index| index.trim_end_matches('/').to_owned()),
+  )
+}
+
+/// Path of the crate file in the index, ie "1/a", "3/f/foo" and "se/rd/serde"
+fn index_path(name: &str) -> String {
+  let name = name.to_lowercase();
+
+  match name.len() {
+    1 => format!("1/{}", name),
+    2 => format!("2/{}", name),
+    3 => format!("3/{}/{}", &name[..1], name),
+    _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+  }
+}
+
+fn parse_index_file(index_file: &[u8], version: &str) -> anyhow::Result<Option<IndexEntry>> {
+  for line in index_file.split(|byte| *byte == b'\n') {
+    if line.iter().all(u8::is_ascii_whitespace) {
+      continue;
+    }
+
+    let entry = serde_json::from_slice::<IndexEntry>(line)?;
+
+    if entry.vers == version {
+      return Ok(Some(entry));
+    }
+  }
+
+  Ok(None)
+}
+
+/// Start of a registry response body for error messages
+pub fn response_excerpt(bytes: &[u8]) -> String {
+  const EXCERPT_CHARS: usize = 200;
+
+  let body = String::from_utf8_lossy(bytes);
+  let body = body.trim();
+
+  match body.char_indices().nth(EXCERPT_CHARS) {
+    Some((end, _)) => format!("{}...", &body[..end]),
+    None => body.to_owned(),
+  }
+}
+
+/// Entry of `name@version` in the sparse index at `index` (without the "sparse+" prefix), Err with
+/// the response when the crate or the version is not in the index yet
+pub async fn sparse_index_entry(
+  index: &str,
+  name: &str,
+  version: &str,
+) -> anyhow::Result<Result<IndexEntry, String>> {
+  let uri = format!("{}/{}", index.trim_end_matches('/'), index_path(name));
+
+  let (status, bytes) = http_get(&uri)
+    .await
+    .with_context(|| format!("Unable to fetch the index file at {}", uri))?;
+
+  match status {
+    StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS => {
+      Ok(Err(format!("{} from {}", status, uri)))
+    }
+    status if status.is_success() => Ok(
+      parse_index_file(&bytes, version)
+        .with_context(|| format!("Invalid index file at {}", uri))?
+        .ok_or_else(|| format!("{} from {} without version {}", status, uri, version)),
+    ),
+    status => Err(anyhow::anyhow!(
+      "Unable to fetch the index file at {}, got {}",
+      uri,
+      status
+    )),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn env_key() {
+    assert_eq!(super::env_key("my-registry"), "MY_REGISTRY");
+  }
+
+  #[test]
+  fn registry_index() {
+    let document = r#"
+[registry]
+default = "my-registry"
+
+[registries.my-registry]
+index = "sparse+https://my-registry.example.com/index/"
+"#
+    .parse::<Document>()
+    .unwrap();
+
+    assert_eq!(
+      super::registry_index(&document, "my-registry"),
+      Some("sparse+https://my-registry.example.com/index/".to_owned())
+    );
+    assert_eq!(super::registry_index(&document, "other-registry"), None);
+    assert_eq!(default_registry(&document), Some("my-registry".to_owned()));
+  }
+
+  #[test]
+  fn index_path() {
+    assert_eq!(super::index_path("a"), "1/a");
+    assert_eq!(super::index_path("ab"), "2/ab");
+    assert_eq!(super::index_path("abc"), "3/a/abc");
+    assert_eq!(super::index_path("Serde"), "se/rd/serde");
+  }
+
+  #[test]
+  fn parse_index_file() {
+    let index_file =
+      br#"{"name":"mol","vers":"0.1.0","deps":[],"cksum":"","features":{},"yanked":false}
+{"name":"mol","vers":"0.2.0","deps":[],"cksum":"","features":{},"yanked":true}
+"#;
+
+    assert_eq!(
+      super::parse_index_file(index_file, "0.2.0").unwrap(),
+      Some(IndexEntry {
+        name: "mol".to_owned(),
+        vers: "0.2.0".to_owned(),
+        yanked: true,
+      })
+    );
+    assert_eq!(super::parse_index_file(index_file, "0.3.0").unwrap(), None);
+  }
+
+  #[test]
+  fn response_excerpt() {
+    assert_eq!(super::response_excerpt(b"  not found\n"), "not found");
+    assert_eq!(
+      super::response_excerpt("x".repeat(300).as_bytes()),
+      format!("{}...", "x".repeat(200))
+    );
+  }
+
+  /// Serves a single http response on a local port, stands in for a sparse index
+  async fn serve_once(status: &'static str, body: &'static str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+      let (mut stream, _) = listener.accept().await.unwrap();
+      let mut request = [0; 1024];
+      let _ = stream.read(&mut request).await.unwrap();
+
+      stream
+        .write_all(
+          format!(
+            "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+          )
+          .as_bytes(),
+        )
+        .await
+        .unwrap();
+    });
+
+    format!("http://{}/index/", address)
+  }
+
+  #[tokio::test]
+  async fn sparse_index_entry() {
+    let index = serve_once(
+      "200 OK",
+      r#"{"name":"mol","vers":"0.1.0","deps":[],"cksum":"","features":{},"yanked":false}"#,
+    )
+    .await;
+
+    assert_eq!(
+      super::sparse_index_entry(&index, "mol", "0.1.0")
+        .await
+        .unwrap(),
+      Ok(IndexEntry {
+        name: "mol".to_owned(),
+        vers: "0.1.0".to_owned(),
+        yanked: false,
+      })
+    );
+
+    let index = serve_once(
+      "200 OK",
+      r#"{"name":"mol","vers":"0.1.0","deps":[],"cksum":"","features":{},"yanked":false}"#,
+    )
+    .await;
+
+    assert_eq!(
+      super::sparse_index_entry(&index, "mol", "0.2.0")
+        .await
+        .unwrap(),
+      Err(format!(
+        "200 OK from {}3/m/mol without version 0.2.0",
+        index
+      ))
+    );
+
+    let index = serve_once("404 Not Found", "").await;
+
+    assert_eq!(
+      super::sparse_index_entry(&index, "mol", "0.1.0")
+        .await
+        .unwrap(),
+      Err(format!("404 Not Found from {}3/m/mol", index))
+    );
+  }
+}
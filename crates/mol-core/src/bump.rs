@@ -1,39 +1,272 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use itertools::Itertools;
 
 use crate::changeset::Changeset;
-use crate::package::PackageGraph;
-use crate::version::{VersionEditor, VersionMod};
+use crate::config::DependentsPolicy;
+use crate::package::{Package, PackageGraph};
+use crate::pre_release::PreRelease;
+use crate::version::{VersionEditor, VersionMod, Versioned};
+
+/// Why a package is released
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReleaseReason {
+  /// Mentioned by a changeset
+  Changeset,
+  /// Depends on a released package
+  Dependent,
+  /// Another member of its fixed group is released
+  FixedGroup,
+}
+
+impl fmt::Display for ReleaseReason {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ReleaseReason::Changeset => write!(f, "changeset"),
+      ReleaseReason::Dependent => write!(f, "dependent"),
+      ReleaseReason::FixedGroup => write!(f, "fixed-group"),
+    }
+  }
+}
 
 #[derive(Debug, Default)]
 pub struct Bump<T> {
   changesets: Vec<Changeset<T>>,
   package_update: HashMap<String, VersionMod<T>>,
+  /// Why the package was first released, changesets added later still take precedence
+  package_reason: HashMap<String, ReleaseReason>,
   package_changesets: HashMap<String, HashSet<usize>>,
+  dependents_bump: Option<VersionMod<T>>,
+  dependents: DependentsPolicy,
+  transitive_dependents: bool,
+  pre_release: bool,
+  /// Versions before the pre-release, the current versions are pre-releases in pre-release mode
+  initial_versions: BTreeMap<String, String>,
+  initial_development: bool,
+  stable: Vec<String>,
+  fixed: Vec<Vec<String>>,
+  linked: Vec<Vec<String>>,
 }
 
 impl<'a, T: VersionEditor> Bump<T> {
+  /// Sets the update of the package and its fixed group and raises its linked group, returns the
+  /// packages whose update changed
   fn set_package_update(
-    package_update: &mut HashMap<String, VersionMod<T>>,
+    &mut self,
     name: &str,
     version: VersionMod<T>,
-  ) {
-    if let Some(concat_version) = package_update.get_mut(name) {
-      if &version > concat_version {
-        *concat_version = version;
+    reason: ReleaseReason,
+  ) -> Vec<String> {
+    let mut changed = Vec::new();
+
+    let members = match self.fixed_group(name) {
+      Some(group) => group.clone(),
+      None => vec![name.to_owned()],
+    };
+
+    for member in members {
+      if let Some(concat_version) = self.package_update.get_mut(&member) {
+        if &version > concat_version {
+          *concat_version = version.clone();
+          changed.push(member);
+        }
+      } else {
+        self.package_update.insert(member.clone(), version.clone());
+        self.package_reason.insert(
+          member.clone(),
+          if member == name {
+            reason
+          } else {
+            ReleaseReason::FixedGroup
+          },
+        );
+        changed.push(member);
+      }
+    }
+
+    if let Some(group) = self.linked_group(name).cloned() {
+      if let Some(highest) = group
+        .iter()
+        .filter_map(|member| self.package_update.get(member))
+        .max()
+        .cloned()
+      {
+        for member in &group {
+          if let Some(member_version) = self.package_update.get_mut(member) {
+            if *member_version != highest {
+              *member_version = highest.clone();
+              changed.push(member.clone());
+            }
+          }
+        }
+      }
+    }
+
+    changed
+  }
+
+  pub fn with_dependents_bump(mut self, version: VersionMod<T>) -> Self {
+    self.dependents_bump = Some(version);
+    self
+  }
+
+  pub fn with_dependents(mut self, dependents: DependentsPolicy) -> Self {
+    self.dependents = dependents;
+    self
+  }
+
+  /// Keep walking the dependents of bumped dependents
+  pub fn with_transitive_dependents(mut self, transitive_dependents: bool) -> Self {
+    self.transitive_dependents = transitive_dependents;
+    self
+  }
+
+  /// While in pre-release the next versions are pre-releases that no release requirement allows,
+  /// so `out-of-range` bumps every dependent, and initial development is decided from the versions
+  /// the pre-release was entered with
+  pub fn with_pre_release(mut self, pre_release: Option<&PreRelease>) -> Self {
+    self.pre_release = pre_release.is_some();
+    self.initial_versions = pre_release
+      .map(|pre_release| pre_release.initial_versions.clone())
+      .unwrap_or_default();
+    self
+  }
+
+  /// Changeset bumps of packages in initial development (ie 0.x) are replaced by
+  /// [`VersionEditor::initial_development`] unless the package is listed in `stable`
+  pub fn with_initial_development(
+    mut self,
+    initial_development: bool,
+    stable: Vec<String>,
+  ) -> Self {
+    self.initial_development = initial_development;
+    self.stable = stable;
+    self
+  }
+
+  /// Groups of packages that are always released together with the same version
+  pub fn with_fixed(mut self, fixed: Vec<Vec<String>>) -> Self {
+    self.fixed = fixed;
+    self
+  }
+
+  /// Groups of packages that share the highest bump among the released members
+  pub fn with_linked(mut self, linked: Vec<Vec<String>>) -> Self {
+    self.linked = linked;
+    self
+  }
+
+  pub fn fixed_group(&self, name: &str) -> Option<&Vec<String>> {
+    self
+      .fixed
+      .iter()
+      .find(|group| group.iter().any(|member| member == name))
+  }
+
+  pub fn linked_group(&self, name: &str) -> Option<&Vec<String>> {
+    self
+      .linked
+      .iter()
+      .find(|group| group.iter().any(|member| member == name))
+  }
+
+  /// Highest current version in the fixed group of the package, the version the whole group is
+  /// released from (see [`ReleasePlan`](crate::plan::ReleasePlan))
+  fn group_version<'b>(&self, name: &str, graph: &PackageGraph<'b, T>) -> Option<&'b str> {
+    let members = match self.fixed_group(name) {
+      Some(group) => group.clone(),
+      None => vec![name.to_owned()],
+    };
+
+    members
+      .iter()
+      .filter_map(|member| graph.package(member))
+      .map(|package| package.version.value.as_str())
+      .fold(None, |highest, version| match highest {
+        Some(highest) if T::compare(version, highest).ok() != Some(Ordering::Greater) => {
+          Some(highest)
+        }
+        _ => Some(version),
+      })
+  }
+
+  /// Whether the dependent has to be released for the update of `name`
+  fn requires_bump(&self, name: &str, dependent: &Package<T>, graph: &PackageGraph<'_, T>) -> bool {
+    match self.dependents {
+      DependentsPolicy::Always => true,
+      DependentsPolicy::Never => false,
+      DependentsPolicy::OutOfRange if self.pre_release => true,
+      DependentsPolicy::OutOfRange => {
+        let next_version = self
+          .group_version(name, graph)
+          .zip(self.package_update.get(name))
+          .and_then(|(version, update)| update.apply(version).ok());
+
+        match next_version {
+          Some(next_version) => dependent
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.name == name)
+            .any(|dependency| !T::r#match(&dependency.version, &next_version)),
+          None => true,
+        }
+      }
+    }
+  }
+
+  /// Bumps the dependents of the package and its fixed group by the dependents policy, in
+  /// transitive mode the dependents of every bumped dependent are walked as well
+  fn bump_dependents(&mut self, name: String, graph: &PackageGraph<'_, T>) {
+    let dependents_update = self
+      .dependents_bump
+      .clone()
+      .unwrap_or_else(|| VersionMod::new(T::options().into_iter().min().unwrap_or_default()));
+
+    let mut pending = vec![name];
+    let mut visited = HashSet::new();
+
+    while let Some(name) = pending.pop() {
+      if !visited.insert(name.clone()) {
+        continue;
+      }
+
+      let members = match self.fixed_group(&name) {
+        Some(group) => group.clone(),
+        None => vec![name],
+      };
+
+      for member in members {
+        for package in graph.child_changes(&member) {
+          if self.requires_bump(&member, package, graph) {
+            // linked members raised by the dependent bump are walked like any changed package
+            for changed in self.set_package_update(
+              &package.name,
+              dependents_update.clone(),
+              ReleaseReason::Dependent,
+            ) {
+              if changed != package.name || self.transitive_dependents {
+                pending.push(changed);
+              }
+            }
+          }
+        }
       }
-    } else {
-      package_update.insert(name.to_owned(), version);
     }
   }
 
   pub fn add(&mut self, changeset: Changeset<T>, graph: &PackageGraph<'_, T>) {
-    let min_update = T::options().into_iter().min().unwrap_or_default();
     let index = self.changesets.len();
+    let packages: Vec<(String, VersionMod<T>)> = changeset
+      .packages
+      .iter()
+      .map(|(name, version)| (name.clone(), version.clone()))
+      .collect();
     self.changesets.insert(index, changeset);
-    let changeset = &self.changesets[index];
 
-    for (name, version) in &changeset.packages {
-      if let Some(changesets) = self.package_changesets.get_mut(name) {
+    for (name, version) in packages {
+      if let Some(changesets) = self.package_changesets.get_mut(&name) {
         changesets.insert(index);
       } else {
         self
@@ -41,14 +274,20 @@ impl<'a, T: VersionEditor> Bump<T> {
           .insert(name.clone(), vec![index].into_iter().collect());
       }
 
-      Self::set_package_update(&mut self.package_update, name, version.clone());
-
-      for package in graph.child_changes(name) {
-        Self::set_package_update(
-          &mut self.package_update,
-          &package.name,
-          VersionMod::new(min_update.clone()),
-        );
+      let version = match graph.package(&name) {
+        Some(package) if self.initial_development && !self.stable.contains(&name) => version
+          .initial_development(
+            self
+              .initial_versions
+              .get(&name)
+              .unwrap_or(&package.version.value),
+          ),
+        _ => version,
+      };
+
+      // packages raised through their linked group have to re-evaluate their dependents as well
+      for changed in self.set_package_update(&name, version, ReleaseReason::Changeset) {
+        self.bump_dependents(changed, graph);
       }
     }
   }
@@ -72,6 +311,7 @@ impl<'a, T> PackageBump<'a, T> {
     self.bump.package_changesets.get(self.name).map(|indexes| {
       indexes
         .iter()
+        .sorted()
         .map(|index| &self.bump.changesets[*index])
         .collect()
     })
@@ -84,6 +324,14 @@ impl<'a, T> PackageBump<'a, T> {
   pub fn version(&self) -> Option<&VersionMod<T>> {
     self.bump.package_update.get(self.name)
   }
+
+  pub fn reason(&self) -> Option<ReleaseReason> {
+    if self.bump.package_changesets.contains_key(self.name) {
+      Some(ReleaseReason::Changeset)
+    } else {
+      self.bump.package_reason.get(self.name).copied()
+    }
+  }
 }
 
 #[cfg(test)]
@@ -105,6 +353,7 @@ mod tests {
           .into_iter()
           .collect(),
         message: "Hi".to_owned(),
+        ..Default::default()
       },
       &graph,
     );
@@ -117,6 +366,7 @@ mod tests {
         .into_iter()
         .collect(),
         message: "Too bad we dont play games".to_owned(),
+        ..Default::default()
       },
       &graph,
     );
@@ -153,6 +403,7 @@ mod tests {
           .into_iter()
           .collect(),
         message: "Hi".to_owned(),
+        ..Default::default()
       },
       &graph,
     );
@@ -162,6 +413,7 @@ mod tests {
           .into_iter()
           .collect(),
         message: "Too bad we dont play games".to_owned(),
+        ..Default::default()
       },
       &graph,
     );
@@ -193,18 +445,21 @@ mod tests {
         path: "".into(),
         version: "0.1.0".into(),
         dependencies: vec![],
+        publish: true,
       },
       Package {
         name: "mol".to_owned(),
         path: "".into(),
         version: "0.1.0".into(),
-        dependencies: vec![("mol-core".to_owned(), "0.1".to_owned())],
+        dependencies: vec![Dependency::new("mol-core", "0.1")],
+        publish: true,
       },
       Package {
         name: "mol-cargo".to_owned(),
         path: "".into(),
         version: "0.1.0".into(),
-        dependencies: vec![("mol-core".to_owned(), "0.1".to_owned())],
+        dependencies: vec![Dependency::new("mol-core", "0.1")],
+        publish: true,
       },
     ];
 
@@ -217,6 +472,7 @@ mod tests {
           .into_iter()
           .collect(),
         message: "Too bad we dont play games".to_owned(),
+        ..Default::default()
       },
       &graph,
     );
@@ -232,4 +488,421 @@ mod tests {
       .collect()
     );
   }
+
+  #[test]
+  fn dependecy_bump_configured() {
+    let packages = vec![
+      Package {
+        name: "mol-core".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "mol".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![Dependency::new("mol-core", "0.1")],
+        publish: true,
+      },
+    ];
+
+    let mut bump = Bump::default().with_dependents_bump(VersionMod::new(Semantic::minor()));
+    let graph = packages.as_package_graph();
+
+    bump.add(
+      Changeset {
+        packages: vec![("mol-core".to_owned(), VersionMod::new(Semantic::patch()))]
+          .into_iter()
+          .collect(),
+        message: "Too bad we dont play games".to_owned(),
+        ..Default::default()
+      },
+      &graph,
+    );
+
+    assert_eq!(
+      bump.package_update,
+      vec![
+        ("mol-core".to_owned(), VersionMod::new(Semantic::patch())),
+        ("mol".to_owned(), VersionMod::new(Semantic::minor())),
+      ]
+      .into_iter()
+      .collect()
+    );
+  }
+
+  /// mol-core <- mol-cargo <- mol and foo <- app, bar is unrelated
+  fn packages() -> Vec<Package<Semantic>> {
+    vec![
+      Package {
+        name: "mol-core".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "mol-cargo".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![Dependency::new("mol-core", "0.1")],
+        publish: true,
+      },
+      Package {
+        name: "mol".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![Dependency::new("mol-cargo", "=0.1.0")],
+        publish: true,
+      },
+      Package {
+        name: "foo".to_owned(),
+        path: "".into(),
+        version: "0.1.5".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "bar".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "app".to_owned(),
+        path: "".into(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("foo", "^0.1")],
+        publish: true,
+      },
+    ]
+  }
+
+  fn changeset(name: &str, version: Semantic) -> Changeset<Semantic> {
+    Changeset {
+      packages: vec![(name.to_owned(), VersionMod::new(version))]
+        .into_iter()
+        .collect(),
+      message: "Hi".to_owned(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn dependents_out_of_range() {
+    let packages = packages();
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default().with_dependents(DependentsPolicy::OutOfRange);
+
+    bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    assert_eq!(
+      bump.package_update,
+      vec![("mol-core".to_owned(), VersionMod::new(Semantic::patch()))]
+        .into_iter()
+        .collect()
+    );
+
+    bump.add(changeset("mol-core", Semantic::minor()), &graph);
+
+    assert_eq!(
+      bump.package_update,
+      vec![
+        ("mol-core".to_owned(), VersionMod::new(Semantic::minor())),
+        ("mol-cargo".to_owned(), VersionMod::new(Semantic::patch())),
+      ]
+      .into_iter()
+      .collect()
+    );
+
+    let mut bump = Bump::default()
+      .with_dependents(DependentsPolicy::OutOfRange)
+      .with_pre_release(Some(&PreRelease::default()));
+
+    bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    assert_eq!(
+      bump.package_update.get("mol-cargo"),
+      Some(&VersionMod::new(Semantic::patch()))
+    );
+  }
+
+  #[test]
+  fn dependents_never() {
+    let packages = packages();
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default().with_dependents(DependentsPolicy::Never);
+
+    bump.add(changeset("mol-core", Semantic::major()), &graph);
+
+    assert_eq!(
+      bump.package_update,
+      vec![("mol-core".to_owned(), VersionMod::new(Semantic::major()))]
+        .into_iter()
+        .collect()
+    );
+  }
+
+  #[test]
+  fn dependents_transitive() {
+    let packages = packages();
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default();
+
+    bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    assert_eq!(bump.package_update.get("mol"), None);
+
+    let mut bump = Bump::default().with_transitive_dependents(true);
+
+    bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    assert_eq!(
+      bump.package_update,
+      vec![
+        ("mol-core".to_owned(), VersionMod::new(Semantic::patch())),
+        ("mol-cargo".to_owned(), VersionMod::new(Semantic::patch())),
+        ("mol".to_owned(), VersionMod::new(Semantic::patch())),
+      ]
+      .into_iter()
+      .collect()
+    );
+
+    // mol-cargo's patch is still allowed by `^0.1` but not by mol's exact requirement
+    let mut bump = Bump::default()
+      .with_dependents(DependentsPolicy::OutOfRange)
+      .with_transitive_dependents(true);
+
+    bump.add(changeset("mol-core", Semantic::minor()), &graph);
+
+    assert_eq!(
+      bump.package_update,
+      vec![
+        ("mol-core".to_owned(), VersionMod::new(Semantic::minor())),
+        ("mol-cargo".to_owned(), VersionMod::new(Semantic::patch())),
+        ("mol".to_owned(), VersionMod::new(Semantic::patch())),
+      ]
+      .into_iter()
+      .collect()
+    );
+  }
+
+  #[test]
+  fn initial_development_bump() {
+    let packages = packages();
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default().with_initial_development(true, vec![]);
+
+    bump.add(changeset("mol-core", Semantic::major()), &graph);
+
+    assert_eq!(
+      bump.package_update.get("mol-core"),
+      Some(&VersionMod::new(Semantic::minor()))
+    );
+
+    let mut bump = Bump::default().with_initial_development(true, vec!["mol-core".to_owned()]);
+
+    bump.add(changeset("mol-core", Semantic::major()), &graph);
+
+    assert_eq!(
+      bump.package_update.get("mol-core"),
+      Some(&VersionMod::new(Semantic::major()))
+    );
+
+    // in pre-release the current version is already a pre-release of 1.0.0, the bump is decided
+    // from the version the pre-release was entered with
+    let mut packages = packages.clone();
+    packages[0].version = "1.0.0-beta.0".into();
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default().with_initial_development(true, vec![]);
+
+    bump.add(changeset("mol-core", Semantic::major()), &graph);
+
+    assert_eq!(
+      bump.package_update.get("mol-core"),
+      Some(&VersionMod::new(Semantic::major()))
+    );
+
+    let pre_release = PreRelease::new(
+      "beta".to_owned(),
+      [("mol-core".to_owned(), "0.1.0".to_owned())].into(),
+    );
+    let mut bump = Bump::default()
+      .with_initial_development(true, vec![])
+      .with_pre_release(Some(&pre_release));
+
+    bump.add(changeset("mol-core", Semantic::major()), &graph);
+
+    assert_eq!(
+      bump.package_update.get("mol-core"),
+      Some(&VersionMod::new(Semantic::minor()))
+    );
+  }
+
+  #[test]
+  fn fixed_bump() {
+    let packages = vec![
+      Package {
+        name: "foo".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "foo-derive".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "bar".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![Dependency::new("foo-derive", "0.1")],
+        publish: true,
+      },
+    ];
+
+    let mut bump =
+      Bump::default().with_fixed(vec![vec!["foo".to_owned(), "foo-derive".to_owned()]]);
+    let graph = packages.as_package_graph();
+
+    bump.add(
+      Changeset {
+        packages: vec![("foo".to_owned(), VersionMod::new(Semantic::minor()))]
+          .into_iter()
+          .collect(),
+        message: "Too bad we dont play games".to_owned(),
+        ..Default::default()
+      },
+      &graph,
+    );
+    bump.add(
+      Changeset {
+        packages: vec![("foo-derive".to_owned(), VersionMod::new(Semantic::patch()))]
+          .into_iter()
+          .collect(),
+        message: "Hi".to_owned(),
+        ..Default::default()
+      },
+      &graph,
+    );
+
+    assert_eq!(
+      bump.package_update,
+      vec![
+        ("foo".to_owned(), VersionMod::new(Semantic::minor())),
+        ("foo-derive".to_owned(), VersionMod::new(Semantic::minor())),
+        ("bar".to_owned(), VersionMod::new(Semantic::patch())),
+      ]
+      .into_iter()
+      .collect()
+    );
+    assert_eq!(bump.package("foo").reason(), Some(ReleaseReason::Changeset));
+    assert_eq!(bump.package("bar").reason(), Some(ReleaseReason::Dependent));
+  }
+
+  #[test]
+  fn linked_bump() {
+    let packages = vec![];
+
+    let mut bump = Bump::default().with_linked(vec![vec![
+      "foo".to_owned(),
+      "foo-derive".to_owned(),
+      "foo-macros".to_owned(),
+    ]]);
+    let graph = packages.as_package_graph();
+
+    bump.add(
+      Changeset {
+        packages: vec![("foo".to_owned(), VersionMod::new(Semantic::minor()))]
+          .into_iter()
+          .collect(),
+        message: "Too bad we dont play games".to_owned(),
+        ..Default::default()
+      },
+      &graph,
+    );
+    bump.add(
+      Changeset {
+        packages: vec![("foo-derive".to_owned(), VersionMod::new(Semantic::patch()))]
+          .into_iter()
+          .collect(),
+        message: "Hi".to_owned(),
+        ..Default::default()
+      },
+      &graph,
+    );
+
+    assert_eq!(
+      bump.package_update,
+      vec![
+        ("foo".to_owned(), VersionMod::new(Semantic::minor())),
+        ("foo-derive".to_owned(), VersionMod::new(Semantic::minor())),
+      ]
+      .into_iter()
+      .collect()
+    );
+  }
+
+  #[test]
+  fn dependents_out_of_range_linked() {
+    let packages = packages();
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default()
+      .with_dependents(DependentsPolicy::OutOfRange)
+      .with_linked(vec![vec!["foo".to_owned(), "bar".to_owned()]]);
+
+    bump.add(changeset("foo", Semantic::patch()), &graph);
+
+    assert_eq!(bump.package_update.get("app"), None);
+
+    // foo is raised to a minor by its linked group, 0.2.0 is out of `^0.1`
+    bump.add(changeset("bar", Semantic::minor()), &graph);
+
+    assert_eq!(
+      bump.package_update,
+      vec![
+        ("foo".to_owned(), VersionMod::new(Semantic::minor())),
+        ("bar".to_owned(), VersionMod::new(Semantic::minor())),
+        ("app".to_owned(), VersionMod::new(Semantic::patch())),
+      ]
+      .into_iter()
+      .collect()
+    );
+  }
+
+  #[test]
+  fn dependents_out_of_range_fixed() {
+    let mut packages = packages();
+    packages[4].version = "0.2.0".into();
+    let graph = packages.as_package_graph();
+
+    // foo is released from the group version 0.2.0 so its patch is 0.2.1, out of `^0.1`
+    let mut bump = Bump::default()
+      .with_dependents(DependentsPolicy::OutOfRange)
+      .with_fixed(vec![vec!["foo".to_owned(), "bar".to_owned()]]);
+
+    bump.add(changeset("foo", Semantic::patch()), &graph);
+
+    assert_eq!(
+      bump.package_update.get("app"),
+      Some(&VersionMod::new(Semantic::patch()))
+    );
+    assert_eq!(
+      bump.package("bar").reason(),
+      Some(ReleaseReason::FixedGroup)
+    );
+  }
 }
@@ -0,0 +1,479 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate, Utc};
+
+use crate::error::{VersionBumpError, VersionParseError};
+use crate::semantic::Semantic;
+use crate::version::{VersionEditor, Versioned};
+
+/// Single dot separated segment of a calendar version format, see https://calver.org
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Segment {
+  /// `YYYY` - 2006, 2016, 2106
+  FullYear,
+  /// `YY` - 6, 16, 106
+  ShortYear,
+  /// `0Y` - 06, 16, 106
+  PaddedYear,
+  /// `MM` - 1, 2 ... 11, 12
+  Month,
+  /// `0M` - 01, 02 ... 11, 12
+  PaddedMonth,
+  /// `WW` - 1, 2 ... 52, 53 (iso week)
+  Week,
+  /// `0W` - 01, 02 ... 52, 53 (iso week)
+  PaddedWeek,
+  /// `DD` - 1, 2 ... 30, 31
+  Day,
+  /// `0D` - 01, 02 ... 30, 31
+  PaddedDay,
+  /// `MICRO` - releases within the same date, starts at 0
+  Micro,
+}
+
+impl Segment {
+  fn parse(token: &str) -> Option<Self> {
+    Some(match token {
+      "YYYY" => Segment::FullYear,
+      "YY" => Segment::ShortYear,
+      "0Y" => Segment::PaddedYear,
+      "MM" => Segment::Month,
+      "0M" => Segment::PaddedMonth,
+      "WW" => Segment::Week,
+      "0W" => Segment::PaddedWeek,
+      "DD" => Segment::Day,
+      "0D" => Segment::PaddedDay,
+      "MICRO" => Segment::Micro,
+      _ => return None,
+    })
+  }
+
+  fn is_week(&self) -> bool {
+    matches!(self, Segment::Week | Segment::PaddedWeek)
+  }
+
+  /// Value of the segment for the date, None for `MICRO`, years are iso week-numbering years in
+  /// formats with a week segment so that 2027-01-01 is `26.53` rather than `27.53`
+  fn date_value(&self, date: NaiveDate, week_year: bool) -> Option<u64> {
+    let year = if week_year {
+      date.iso_week().year()
+    } else {
+      date.year()
+    }
+    .max(0) as u64;
+
+    match self {
+      Segment::FullYear => Some(year),
+      Segment::ShortYear | Segment::PaddedYear => Some(year.saturating_sub(2000)),
+      Segment::Month | Segment::PaddedMonth => Some(date.month().into()),
+      Segment::Week | Segment::PaddedWeek => Some(date.iso_week().week().into()),
+      Segment::Day | Segment::PaddedDay => Some(date.day().into()),
+      Segment::Micro => None,
+    }
+  }
+
+  fn format(&self, value: u64) -> String {
+    match self {
+      Segment::PaddedYear | Segment::PaddedMonth | Segment::PaddedWeek | Segment::PaddedDay => {
+        format!("{:02}", value)
+      }
+      _ => value.to_string(),
+    }
+  }
+}
+
+/// Format and date source of a [`CalVer`] version
+pub trait CalVerScheme: Clone + Debug + Default + Hash + Ord + Send + Sync + 'static {
+  /// Dot separated segments, `YYYY`, `YY`, `0Y`, `MM`, `0M`, `WW`, `0W`, `DD`, `0D` and `MICRO`
+  /// ie `YYYY.0M.MICRO` or `YY.MM`, cargo only accepts formats that are valid semver (three
+  /// segments without zero padding)
+  const FORMAT: &'static str;
+
+  /// Date of the next release
+  fn today() -> NaiveDate {
+    Utc::now().date_naive()
+  }
+}
+
+/// `YYYY.MM.MICRO`, a calendar version cargo accepts
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct YearMonthMicro;
+
+impl CalVerScheme for YearMonthMicro {
+  const FORMAT: &'static str = "YYYY.MM.MICRO";
+}
+
+/// Calendar versioning, every bump moves the date segments to the current date and increments
+/// `MICRO` when the date didn't change since the current version
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CalVer<S = YearMonthMicro> {
+  scheme: PhantomData<S>,
+}
+
+struct CalVerValue<'a> {
+  segments: Vec<u64>,
+  pre_release: Option<&'a str>,
+  build: Option<&'a str>,
+}
+
+impl<S: CalVerScheme> CalVer<S> {
+  pub fn micro() -> Self {
+    CalVer {
+      scheme: PhantomData,
+    }
+  }
+
+  fn format(version: &str) -> Result<Vec<Segment>, VersionBumpError> {
+    S::FORMAT
+      .split('.')
+      .map(|token| {
+        Segment::parse(token).ok_or_else(|| {
+          VersionBumpError::new(
+            version,
+            format!("unknown segment \"{}\" in format {}", token, S::FORMAT),
+          )
+        })
+      })
+      .collect()
+  }
+
+  fn parse(value: &str) -> Result<(Vec<Segment>, CalVerValue<'_>), VersionBumpError> {
+    let format = Self::format(value)?;
+
+    let (value_build, build) = match value.split_once('+') {
+      Some((value, build)) => (value, Some(build)),
+      None => (value, None),
+    };
+
+    let (release, pre_release) = match value_build.split_once('-') {
+      Some((release, pre_release)) => (release, Some(pre_release)),
+      None => (value_build, None),
+    };
+
+    let segments = release
+      .split('.')
+      .enumerate()
+      .map(|(index, segment)| {
+        segment.parse::<u64>().map_err(|err| {
+          VersionBumpError::new(
+            value,
+            format!(
+              "segment {} \"{}\" isn't a number, {}",
+              index + 1,
+              segment,
+              err
+            ),
+          )
+        })
+      })
+      .collect::<Result<Vec<u64>, VersionBumpError>>()?;
+
+    if segments.len() != format.len() {
+      return Err(VersionBumpError::new(
+        value,
+        format!(
+          "expected {} segments for format {}, found {}",
+          format.len(),
+          S::FORMAT,
+          segments.len()
+        ),
+      ));
+    }
+
+    Ok((
+      format,
+      CalVerValue {
+        segments,
+        pre_release,
+        build,
+      },
+    ))
+  }
+
+  fn write(format: &[Segment], segments: &[u64]) -> String {
+    format
+      .iter()
+      .zip(segments)
+      .map(|(segment, value)| segment.format(*value))
+      .collect::<Vec<String>>()
+      .join(".")
+  }
+}
+
+impl<S: CalVerScheme> Versioned for CalVer<S> {
+  fn apply(&self, current: &str) -> Result<String, VersionBumpError> {
+    let (format, current_value) = Self::parse(current)?;
+    let today = S::today();
+
+    let date = |segments: &[u64]| -> Vec<u64> {
+      format
+        .iter()
+        .zip(segments)
+        .filter(|(segment, _)| **segment != Segment::Micro)
+        .map(|(_, value)| *value)
+        .collect()
+    };
+
+    let week_year = format.iter().any(Segment::is_week);
+
+    let mut next = format
+      .iter()
+      .map(|segment| segment.date_value(today, week_year).unwrap_or(0))
+      .collect::<Vec<u64>>();
+
+    // the date didn't move (or the clock is behind the current version) so the release is told
+    // apart by MICRO, a pre-release already precedes its release
+    if date(&next) <= date(&current_value.segments) {
+      next = current_value.segments.clone();
+
+      if current_value.pre_release.is_none() {
+        let micro = format
+          .iter()
+          .position(|segment| *segment == Segment::Micro)
+          .ok_or_else(|| {
+            VersionBumpError::new(
+              current,
+              format!(
+                "format {} has no MICRO segment for a second release on the same date",
+                S::FORMAT
+              ),
+            )
+          })?;
+
+        next[micro] += 1;
+      }
+    }
+
+    Ok(Self::write(&format, &next))
+  }
+
+  fn apply_pre_release(
+    &self,
+    initial: &str,
+    current: &str,
+    tag: &str,
+  ) -> Result<String, VersionBumpError> {
+    let target = self.apply(initial)?;
+
+    let (format, current) = Self::parse(current)?;
+
+    let next_pre = current
+      .pre_release
+      .filter(|_| Self::write(&format, &current.segments) == target)
+      .and_then(|pre_release| pre_release.strip_prefix(tag))
+      .and_then(|counter| counter.strip_prefix('.'))
+      .and_then(|counter| counter.parse::<u32>().ok())
+      .map(|counter| counter + 1)
+      .unwrap_or(0);
+
+    Ok(format!("{}-{}.{}", target, tag, next_pre))
+  }
+}
+
+impl<S: CalVerScheme> VersionEditor for CalVer<S> {
+  fn release(version: &str) -> Result<String, VersionBumpError> {
+    let (format, value) = Self::parse(version)?;
+
+    Ok(Self::write(&format, &value.segments))
+  }
+
+  fn compare(version: &str, other: &str) -> Result<Ordering, VersionBumpError> {
+    let (_, version) = Self::parse(version)?;
+    let (_, other) = Self::parse(other)?;
+
+    Ok(version.segments.cmp(&other.segments).then_with(|| {
+      match (version.pre_release, other.pre_release) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(pre_release), Some(other)) => {
+          match (
+            semver::Prerelease::new(pre_release),
+            semver::Prerelease::new(other),
+          ) {
+            (Ok(pre_release), Ok(other)) => pre_release.cmp(&other),
+            _ => pre_release.cmp(other),
+          }
+        }
+      }
+    }))
+  }
+
+  /// Requirements are cargo requirements, see [`Semantic::mask`]
+  fn mask(mask: &str, version: &str) -> String {
+    Semantic::mask(mask, version)
+  }
+
+  fn rewrite(mask: &str, version: &str) -> String {
+    Semantic::rewrite(mask, version)
+  }
+
+  fn r#match(mask: &str, version: &str) -> bool {
+    Semantic::r#match(mask, version)
+  }
+
+  fn with_build_metadata(next: &str, current: &str) -> Result<String, VersionBumpError> {
+    let (_, current) = Self::parse(current)?;
+
+    Ok(match current.build {
+      Some(build) => format!("{}+{}", next.split('+').next().unwrap_or(next), build),
+      None => next.to_owned(),
+    })
+  }
+
+  fn options() -> Vec<Self> {
+    vec![Self::micro()]
+  }
+
+  fn initial_development(&self, _: &str) -> Self {
+    self.clone()
+  }
+
+  fn snapshot(next: &str, snapshot: &str) -> Result<String, VersionBumpError> {
+    let (_, value) = Self::parse(next)?;
+
+    Ok(match value.pre_release {
+      Some(_) => format!("{}.{}", next, snapshot),
+      None => format!("{}-{}", next, snapshot),
+    })
+  }
+}
+
+impl<S: CalVerScheme> FromStr for CalVer<S> {
+  type Err = VersionParseError<CalVer<S>>;
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value.to_lowercase().as_str() {
+      "micro" => Ok(CalVer::micro()),
+      _ => Err(VersionParseError::<CalVer<S>>::from(value)),
+    }
+  }
+}
+
+impl<S> fmt::Display for CalVer<S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("micro")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::version::VersionMod;
+
+  macro_rules! scheme {
+    ($name:ident, $format:literal, $year:literal, $month:literal, $day:literal) => {
+      #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+      struct $name;
+
+      impl CalVerScheme for $name {
+        const FORMAT: &'static str = $format;
+
+        fn today() -> NaiveDate {
+          NaiveDate::from_ymd_opt($year, $month, $day).unwrap()
+        }
+      }
+    };
+  }
+
+  scheme!(October, "YYYY.0M.MICRO", 2026, 10, 18);
+  scheme!(ShortMonth, "YY.MM", 2026, 10, 18);
+  scheme!(Weekly, "0Y.0W.MICRO", 2026, 1, 5);
+  scheme!(NewYear, "0Y.0W.MICRO", 2027, 1, 1);
+  scheme!(FirstWeek, "0Y.0W.MICRO", 2027, 1, 4);
+
+  #[test]
+  fn apply() {
+    let version = VersionMod::new(CalVer::<October>::micro());
+
+    assert_eq!(version.apply("2026.09.4").unwrap(), "2026.10.0");
+    assert_eq!(version.apply("2026.10.0").unwrap(), "2026.10.1");
+    assert_eq!(version.apply("2025.12.7").unwrap(), "2026.10.0");
+    assert_eq!(version.apply("2026.10.1-beta.2").unwrap(), "2026.10.1");
+    assert_eq!(version.apply("2026.11.0").unwrap(), "2026.11.1");
+
+    let version = VersionMod::new(CalVer::<ShortMonth>::micro());
+
+    assert_eq!(version.apply("26.9").unwrap(), "26.10");
+    assert!(version.apply("26.10").is_err());
+
+    let version = VersionMod::new(CalVer::<Weekly>::micro());
+
+    assert_eq!(version.apply("25.52.3").unwrap(), "26.02.0");
+
+    // 2027-01-01 is in the last iso week of 2026
+    let version = VersionMod::new(CalVer::<NewYear>::micro());
+
+    assert_eq!(version.apply("26.52.1").unwrap(), "26.53.0");
+    assert_eq!(version.apply("26.53.0").unwrap(), "26.53.1");
+
+    let version = VersionMod::new(CalVer::<FirstWeek>::micro());
+
+    assert_eq!(version.apply("26.53.1").unwrap(), "27.01.0");
+  }
+
+  #[test]
+  fn apply_pre_release() {
+    let version = VersionMod::new(CalVer::<October>::micro());
+
+    assert_eq!(
+      version
+        .apply_pre_release("2026.09.4", "2026.09.4", "beta")
+        .unwrap(),
+      "2026.10.0-beta.0"
+    );
+    assert_eq!(
+      version
+        .apply_pre_release("2026.09.4", "2026.10.0-beta.0", "beta")
+        .unwrap(),
+      "2026.10.0-beta.1"
+    );
+  }
+
+  #[test]
+  fn parse_error() {
+    assert_eq!(
+      CalVer::<October>::release("2026.x.1")
+        .unwrap_err()
+        .to_string(),
+      "Invalid version \"2026.x.1\", segment 2 \"x\" isn't a number, invalid digit found in string"
+    );
+    assert_eq!(
+      CalVer::<October>::release("2026.10")
+        .unwrap_err()
+        .to_string(),
+      "Invalid version \"2026.10\", expected 3 segments for format YYYY.0M.MICRO, found 2"
+    );
+  }
+
+  #[test]
+  fn compare() {
+    assert_eq!(
+      CalVer::<October>::compare("2026.09.4", "2026.10.0").unwrap(),
+      Ordering::Less
+    );
+    assert_eq!(
+      CalVer::<October>::compare("2026.10.0", "2026.10.0-beta.11").unwrap(),
+      Ordering::Greater
+    );
+    assert_eq!(
+      CalVer::<October>::compare("2026.10.0-beta.2", "2026.10.0-beta.11").unwrap(),
+      Ordering::Less
+    );
+  }
+
+  #[test]
+  fn from_str() {
+    assert_eq!(
+      "micro".parse::<CalVer<October>>().unwrap(),
+      CalVer::<October>::micro()
+    );
+    assert!("major".parse::<CalVer<October>>().is_err());
+    assert_eq!(CalVer::<October>::micro().to_string(), "micro");
+  }
+}
@@ -7,9 +7,10 @@ use itertools::Itertools;
 use tokio::{fs, io::AsyncWriteExt};
 
 use crate::bump::PackageBump;
+use crate::calver::{CalVer, CalVerScheme};
 use crate::changeset::Changeset;
 use crate::semantic::Semantic;
-use crate::version::{Version, VersionMod, Versioned};
+use crate::version::{Version, VersionEditor, VersionMod, Versioned};
 
 fn capitalize(s: &str) -> String {
   let mut c = s.chars();
@@ -38,9 +39,12 @@ fn fill_output<V: AsChangelogFmt + Versioned + Ord>(
   output
 }
 
+/// Changes grouped by bump, a changeset is never filed above the `effective` bump of the package
+/// (ie a major in initial development that only bumps the minor)
 fn create_patches<V>(
   package_name: &str,
   changesets: Vec<&Changeset<V>>,
+  effective: Option<&VersionMod<V>>,
 ) -> HashMap<VersionMod<V>, Vec<String>>
 where
   V: AsChangelogFmt + Clone + Hash + Ord + Versioned,
@@ -51,6 +55,11 @@ where
     let changeset_summary = changset.as_changelog_fmt();
 
     if let Some(version) = changset.packages.get(package_name) {
+      let version = match effective {
+        Some(effective) => version.min(effective),
+        None => version,
+      };
+
       if let Some(changes) = patches.get_mut(version) {
         changes.push(changeset_summary);
       } else {
@@ -62,6 +71,93 @@ where
   patches
 }
 
+fn parse_section_patches<V: VersionEditor>(
+  section: &[&str],
+) -> Option<Vec<(VersionMod<V>, String)>> {
+  let headers: Vec<(String, VersionMod<V>)> = VersionMod::<V>::options()
+    .into_iter()
+    .map(|version| (version.as_changelog_fmt().trim_end().to_owned(), version))
+    .collect();
+
+  let mut current = None;
+  let mut patches: Vec<(VersionMod<V>, String)> = Vec::new();
+
+  for line in section {
+    if line.starts_with("### ") {
+      current = Some(
+        headers
+          .iter()
+          .find(|(header, _)| header == line.trim_end())
+          .map(|(_, version)| version.clone())?,
+      );
+    } else if line.starts_with("- ") {
+      patches.push((current.clone()?, format!("{}\n", line)));
+    } else if line.starts_with("  ") {
+      let (_, changes) = patches.last_mut()?;
+      changes.push_str(line);
+      changes.push('\n');
+    } else if !line.trim().is_empty() {
+      return None;
+    }
+  }
+
+  Some(patches)
+}
+
+fn fold_pre_release_sections<V: VersionEditor>(
+  changelog: &str,
+  next_version: &Version<V>,
+) -> Option<String> {
+  let mut lines = changelog.split('\n');
+
+  let mut output: Vec<String> = vec![lines.next()?.to_owned()];
+  let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+
+  for line in lines {
+    if let Some(version) = line.strip_prefix("## ") {
+      sections.push((version.trim(), Vec::new()));
+    } else if let Some((_, section)) = sections.last_mut() {
+      section.push(line);
+    } else {
+      output.push(line.to_owned());
+    }
+  }
+
+  let mut folded_index = None;
+  let mut patches: HashMap<VersionMod<V>, Vec<String>> = HashMap::new();
+
+  for (version, section) in sections {
+    let is_pre_release = version != next_version.value
+      && V::release(version)
+        .map(|release| release == next_version.value)
+        .unwrap_or(false);
+
+    let section_patches = if is_pre_release {
+      parse_section_patches::<V>(&section)
+    } else {
+      None
+    };
+
+    if let Some(section_patches) = section_patches {
+      for (version, changes) in section_patches {
+        patches.entry(version).or_default().push(changes);
+      }
+
+      if folded_index.is_none() {
+        folded_index = Some(output.len());
+        output.push(String::new());
+      }
+    } else {
+      output.push(format!("## {}", version));
+      output.extend(section.into_iter().map(str::to_owned));
+    }
+  }
+
+  output[folded_index?] = fill_output(next_version, &patches);
+
+  Some(output.join("\n"))
+}
+
 pub struct Changelog;
 
 impl Changelog {
@@ -79,7 +175,7 @@ impl Changelog {
 
     if let Some(patches) = package_bump
       .changesets()
-      .map(|changesets| create_patches(package_name, changesets))
+      .map(|changesets| create_patches(package_name, changesets, package_bump.version()))
     {
       if dry_run {
         println!(
@@ -119,6 +215,35 @@ impl Changelog {
 
     Ok(())
   }
+
+  pub async fn fold_pre_release<T, V>(
+    changelog_path: T,
+    next_version: Version<V>,
+    dry_run: bool,
+  ) -> std::io::Result<()>
+  where
+    T: AsRef<Path> + Debug,
+    V: VersionEditor,
+  {
+    let changelog = match fs::read_to_string(&changelog_path).await {
+      Ok(changelog) => changelog,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+      Err(err) => return Err(err),
+    };
+
+    if let Some(output) = fold_pre_release_sections(&changelog, &next_version) {
+      if dry_run {
+        println!(
+          "dry_run - fold pre-release changelog {:?} into {}",
+          changelog_path, next_version.value
+        );
+      } else {
+        fs::write(&changelog_path, output).await?;
+      }
+    }
+
+    Ok(())
+  }
 }
 
 pub trait AsChangelogFmt: Sized {
@@ -153,6 +278,12 @@ impl AsChangelogFmt for Semantic {
   }
 }
 
+impl<S: CalVerScheme> AsChangelogFmt for CalVer<S> {
+  fn as_changelog_fmt(&self) -> String {
+    capitalize(&self.to_string())
+  }
+}
+
 impl<T: AsChangelogFmt> AsChangelogFmt for VersionMod<T> {
   fn as_changelog_fmt(&self) -> String {
     format!("### {} Changes\n", self.version.as_changelog_fmt())
@@ -164,3 +295,115 @@ impl<T> AsChangelogFmt for Version<T> {
     format!("## {}\n", self.value)
   }
 }
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::semantic::Semantic;
+
+  #[test]
+  fn fold_pre_release() {
+    let changelog = "# mol
+
+## 0.4.0-beta.1
+
+### Patch Changes
+
+- fix things
+
+## 0.4.0-beta.0
+
+### Minor Changes
+
+- add things
+  in two lines
+
+### Patch Changes
+
+- fix other things
+
+## 0.3.0
+
+### Minor Changes
+
+- old things
+";
+
+    let folded =
+      fold_pre_release_sections::<Semantic>(changelog, &Version::<Semantic>::from("0.4.0"));
+
+    assert_eq!(
+      folded,
+      Some(
+        "# mol
+
+## 0.4.0
+
+### Minor Changes
+
+- add things
+  in two lines
+
+### Patch Changes
+
+- fix things
+
+- fix other things
+
+## 0.3.0
+
+### Minor Changes
+
+- old things
+"
+        .to_owned()
+      )
+    );
+  }
+
+  #[test]
+  fn fold_pre_release_without_pre_releases() {
+    let changelog = "# mol
+
+## 0.3.0
+
+### Minor Changes
+
+- old things
+";
+
+    let folded =
+      fold_pre_release_sections::<Semantic>(changelog, &Version::<Semantic>::from("0.4.0"));
+
+    assert_eq!(folded, None);
+  }
+
+  #[test]
+  fn create_patches_effective_bump() {
+    let major = Changeset {
+      packages: [("mol-core".to_owned(), VersionMod::new(Semantic::major()))].into(),
+      message: "Breaking".to_owned(),
+      ..Default::default()
+    };
+    let patch = Changeset {
+      packages: [("mol-core".to_owned(), VersionMod::new(Semantic::patch()))].into(),
+      message: "Fix".to_owned(),
+      ..Default::default()
+    };
+
+    let patches = create_patches(
+      "mol-core",
+      vec![&major, &patch],
+      Some(&VersionMod::new(Semantic::minor())),
+    );
+
+    assert_eq!(
+      patches.keys().sorted().collect::<Vec<_>>(),
+      vec![
+        &VersionMod::new(Semantic::patch()),
+        &VersionMod::new(Semantic::minor())
+      ]
+    );
+  }
+}
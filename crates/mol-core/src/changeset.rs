@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -12,6 +13,8 @@ use crate::version::{VersionMod, Versioned};
 
 #[derive(Debug, Default)]
 pub struct Changeset<T> {
+  /// File stem of the changeset file, empty when the changeset wasn't read from a file
+  pub id: String,
   pub packages: HashMap<String, VersionMod<T>>,
   pub message: String,
 }
@@ -42,9 +45,17 @@ impl<T> Changeset<T> {
     }
   }
 
-  pub fn random_file_name() -> String {
+  pub fn random_id() -> String {
     let mut rng = rand::thread_rng();
-    format!("{}-{}.md", rng.gen::<Word>(), rng.gen::<Word>())
+    format!("{}-{}", rng.gen::<Word>(), rng.gen::<Word>())
+  }
+
+  pub fn random_file_name() -> String {
+    format!("{}.md", Self::random_id())
+  }
+
+  pub fn file_name(&self) -> String {
+    format!("{}.md", self.id)
   }
 }
 
@@ -102,28 +113,29 @@ where
     }
 
     Ok(Self {
+      id: String::new(),
       packages,
       message: lines.collect::<Vec<&str>>().join("\n").trim().to_owned(),
     })
   }
 }
 
-impl<T> ToString for Changeset<T>
+impl<T> fmt::Display for Changeset<T>
 where
   T: Versioned + Ord + ToString,
 {
-  fn to_string(&self) -> String {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     let mut output = vec![];
 
     output.extend(b"---\n");
     for (package, version) in self.packages.iter().sorted() {
-      output.extend(format!("\"{}\": {}\n", package, version.to_string()).as_bytes())
+      output.extend(format!("\"{}\": {}\n", package, version).as_bytes())
     }
     output.extend(b"---\n\n");
     output.extend(self.message.as_bytes());
     output.push(b'\n');
 
-    String::from_utf8(output).unwrap()
+    f.write_str(&String::from_utf8(output).unwrap())
   }
 }
 
@@ -190,6 +202,7 @@ Do cool stuff
         .into_iter()
         .collect(),
       message: "Do cool stuff".to_string(),
+      ..Default::default()
     };
 
     assert_eq!(
@@ -213,6 +226,7 @@ Do cool stuff
       .into_iter()
       .collect(),
       message: "Do cool stuff".to_string(),
+      ..Default::default()
     };
 
     assert_eq!(
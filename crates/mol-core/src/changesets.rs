@@ -1,43 +1,117 @@
 use anyhow::Context;
 use std::ffi::OsStr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use tokio::fs;
 
 use crate::bump::Bump;
 use crate::changeset::Changeset;
+use crate::config::{Config, DEFAULT_CONFIG_PATH};
 use crate::package::PackageGraph;
-use crate::version::VersionEditor;
+use crate::pre_release::PreRelease;
+use crate::version::{VersionEditor, VersionMod};
 
 pub struct Changesets {
   pub directory: PathBuf,
+  pub config: Config,
 }
 
 impl Changesets {
+  /// Loads the config from [`DEFAULT_CONFIG_PATH`] whatever its `directory` is
+  pub async fn load() -> anyhow::Result<Self> {
+    Ok(Config::load(&*DEFAULT_CONFIG_PATH).await?.into())
+  }
+
   fn readme_path(&self) -> PathBuf {
     let mut readme_path = self.directory.clone();
     readme_path.push("README.md");
     readme_path
   }
 
+  pub fn pre_release_path(&self) -> PathBuf {
+    let mut pre_release_path = self.directory.clone();
+    pre_release_path.push("pre.toml");
+    pre_release_path
+  }
+
+  pub async fn load_pre_release(&self) -> anyhow::Result<Option<PreRelease>> {
+    PreRelease::load(self.pre_release_path()).await
+  }
+
   pub fn validate(&self) -> bool {
     self.directory.exists() && self.readme_path().exists()
   }
 
-  pub async fn initialize(&self) -> Result<(), std::io::Error> {
+  pub async fn initialize(&self) -> anyhow::Result<()> {
     if !self.directory.exists() {
       fs::create_dir(&self.directory).await?;
     }
 
     fs::write(self.readme_path(), b"# Changesets directory\n\nThis directory is for changeset files, can be createted with `mol add`\n\n").await?;
 
+    if !DEFAULT_CONFIG_PATH.exists() {
+      if let Some(config_dir) = DEFAULT_CONFIG_PATH.parent() {
+        fs::create_dir_all(config_dir).await?;
+      }
+
+      self.config.save(&*DEFAULT_CONFIG_PATH).await?;
+    }
+
     Ok(())
   }
 
+  /// Consume every changeset, including the ones already released as part of the pre-release
   pub async fn consume<V: VersionEditor>(
     &self,
     package_graph: &PackageGraph<'_, V>,
+    pre_release: Option<&PreRelease>,
+  ) -> anyhow::Result<(Vec<PathBuf>, Bump<V>)> {
+    self
+      .consume_where(package_graph, pre_release, |_| true)
+      .await
+  }
+
+  /// Consume the changesets that weren't released yet, while in pre-release the changesets that
+  /// were already pre-released are skipped
+  pub async fn consume_pending<V: VersionEditor>(
+    &self,
+    package_graph: &PackageGraph<'_, V>,
+    pre_release: Option<&PreRelease>,
   ) -> anyhow::Result<(Vec<PathBuf>, Bump<V>)> {
-    let mut bump = Bump::default();
+    self
+      .consume_where(package_graph, pre_release, |path| {
+        pre_release
+          .map(|pre_release| !pre_release.contains(path))
+          .unwrap_or(true)
+      })
+      .await
+  }
+
+  pub async fn consume_where<V: VersionEditor, F: Fn(&Path) -> bool>(
+    &self,
+    package_graph: &PackageGraph<'_, V>,
+    pre_release: Option<&PreRelease>,
+    filter: F,
+  ) -> anyhow::Result<(Vec<PathBuf>, Bump<V>)> {
+    let mut bump = match &self.config.dependents_bump {
+      Some(dependents_bump) => {
+        Bump::default().with_dependents_bump(VersionMod::from_str(dependents_bump).map_err(
+          |_| anyhow::anyhow!("Invalid dependents_bump \"{}\" in config", dependents_bump),
+        )?)
+      }
+      None => Bump::default(),
+    }
+    .with_dependents(self.config.dependents)
+    .with_transitive_dependents(self.config.transitive_dependents)
+    .with_pre_release(pre_release)
+    .with_initial_development(self.config.initial_development, self.config.stable.clone())
+    .with_fixed(self.config.fixed_groups())
+    .with_linked(self.config.linked.clone());
+    let ignore = self
+      .config
+      .ignore_globs()
+      .context("Invalid ignore pattern in config")?;
+
     let mut changeset_files_paths = Vec::new();
 
     let mut changeset_files = fs::read_dir(&self.directory).await.with_context(|| {
@@ -47,12 +121,19 @@ impl Changesets {
       )
     })?;
 
+    let mut changeset_paths = Vec::new();
+
     while let Some(changeset) = changeset_files.next_entry().await? {
-      let changeset_path = changeset.path();
+      changeset_paths.push(changeset.path());
+    }
 
+    changeset_paths.sort();
+
+    for changeset_path in changeset_paths {
       if let Some(ext) = changeset_path.extension() {
         if ext == "md" {
-          if Some(OsStr::new("README.md")) == changeset_path.file_name() {
+          if Some(OsStr::new("README.md")) == changeset_path.file_name() || !filter(&changeset_path)
+          {
             continue;
           }
 
@@ -60,11 +141,26 @@ impl Changesets {
             .await
             .with_context(|| format!("Unable to read the changeset at {:?}", changeset_path))?;
 
-          bump.add(
-            Changeset::<V>::parse(&raw_changeset)
-              .with_context(|| format!("Unable to parse changeset at {:?}", changeset_path))?,
-            package_graph,
-          );
+          let mut changeset = Changeset::<V>::parse(&raw_changeset)
+            .with_context(|| format!("Unable to parse changeset at {:?}", changeset_path))?;
+
+          if let Some(name) = changeset
+            .packages
+            .keys()
+            .find(|name| ignore.is_match(name.as_str()))
+          {
+            return Err(anyhow::anyhow!(
+              "Changeset at {:?} mentions the ignored package \"{}\", remove it from the changeset or from the ignore list in config",
+              changeset_path,
+              name
+            ));
+          }
+
+          if let Some(id) = changeset_path.file_stem().and_then(|stem| stem.to_str()) {
+            changeset.id = id.to_owned();
+          }
+
+          bump.add(changeset, package_graph);
 
           changeset_files_paths.push(changeset_path);
         }
@@ -77,8 +173,15 @@ impl Changesets {
 
 impl Default for Changesets {
   fn default() -> Self {
+    Config::default().into()
+  }
+}
+
+impl From<Config> for Changesets {
+  fn from(config: Config) -> Self {
     Changesets {
-      directory: [".changeset"].iter().collect(),
+      directory: config.directory.clone(),
+      config,
     }
   }
 }
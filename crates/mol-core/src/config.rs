@@ -0,0 +1,409 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Context;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+lazy_static::lazy_static! {
+  pub static ref DEFAULT_CONFIG_PATH: PathBuf = [".changeset", "config.toml"].iter().collect();
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Config {
+  /// Directory where changeset files and the pre-release state are stored, the config is read
+  /// before this is known so it always stays at [`DEFAULT_CONFIG_PATH`]
+  pub directory: PathBuf,
+  /// Name of the changelog file that is written next to every package manifest
+  pub changelog: String,
+  /// Packages that mol should leave alone, supports globs ie "*-examples"
+  pub ignore: Vec<String>,
+  /// Extra arguments for the package manager build that runs after `mol version`
+  pub build_args: Vec<String>,
+  /// Extra arguments for the package manager publish
+  pub publish_args: Vec<String>,
+  /// Bump applied to dependents of a changed package (defaults to the smallest bump)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub dependents_bump: Option<String>,
+  /// When dependents of a changed package are bumped
+  pub dependents: DependentsPolicy,
+  /// Dependents of bumped dependents are bumped as well
+  pub transitive_dependents: bool,
+  /// A `major` changeset on a 0.x package is released as a minor, which is already breaking under
+  /// 0.x, unless the package is listed in `stable`
+  pub initial_development: bool,
+  /// Packages that opted into 1.0, their next `major` changeset releases 1.0.0
+  pub stable: Vec<String>,
+  /// Build metadata of the current version (ie `+build5`) is kept in the next version
+  pub keep_build_metadata: bool,
+  /// Groups of packages that are always released together with the same version
+  pub fixed: Vec<Vec<String>>,
+  /// Groups of packages that share a single version in their manifests (ie cargo members that
+  /// inherit the workspace version), found by the package manager and never saved
+  #[serde(skip)]
+  pub shared_version: Vec<Vec<String>>,
+  /// Groups of packages where the released members share the highest bump
+  pub linked: Vec<Vec<String>>,
+  /// Drop dev-dependency edges that close a dependency cycle, when false any cycle fails
+  pub ignore_dev_cycles: bool,
+  /// Registry that packages are published to
+  pub registry: Registry,
+  /// How long `mol publish` waits for a published version to show up in the registry
+  pub publish_wait: PublishWait,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependentsPolicy {
+  /// Every dependent of a changed package is bumped
+  #[default]
+  Always,
+  /// Only dependents whose requirement doesn't allow the new version anymore are bumped
+  OutOfRange,
+  /// Dependents are only bumped by their own changesets
+  Never,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Registry {
+  /// Name of the registry as configured for the package manager, None for the default registry
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  /// Base url of the registry api, resolved by the package manager when None
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub api: Option<String>,
+  /// Index of the registry, resolved by the package manager when None
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub index: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct PublishWait {
+  /// Seconds to wait per package, every published package gets its own timeout
+  pub timeout: u64,
+  /// Maximum number of registry checks per package
+  pub max_attempts: u32,
+  /// Delay in milliseconds after the first check, doubled after every check
+  pub initial_delay: u64,
+  /// Upper bound in milliseconds for the delay between checks
+  pub max_delay: u64,
+}
+
+impl PublishWait {
+  /// Exponential delay after the check number `attempt` (starting at 1) without jitter
+  pub fn backoff(&self, attempt: u32) -> Duration {
+    let delay = self
+      .initial_delay
+      .saturating_mul(1 << attempt.saturating_sub(1).min(32))
+      .min(self.max_delay);
+
+    Duration::from_millis(delay)
+  }
+
+  /// Backoff where a random half of the delay is dropped so parallel publishes don't poll in sync
+  pub fn delay(&self, attempt: u32) -> Duration {
+    let backoff = self.backoff(attempt);
+
+    backoff / 2 + rand::thread_rng().gen_range(Duration::ZERO..=backoff / 2)
+  }
+}
+
+impl Default for PublishWait {
+  fn default() -> Self {
+    PublishWait {
+      timeout: 600,
+      max_attempts: 30,
+      initial_delay: 1000,
+      max_delay: 30000,
+    }
+  }
+}
+
+impl Registry {
+  /// Registry selected by name (ie from the command line), the configured api and index are only
+  /// kept when they belong to the same registry
+  pub fn select(&self, name: Option<&str>) -> Registry {
+    match name {
+      Some(name) if self.name.as_deref() != Some(name) => Registry {
+        name: Some(name.to_owned()),
+        api: None,
+        index: None,
+      },
+      _ => self.clone(),
+    }
+  }
+}
+
+impl Config {
+  pub async fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+
+    if !path.exists() {
+      return Ok(Config::default());
+    }
+
+    let raw_config = fs::read_to_string(path)
+      .await
+      .with_context(|| format!("Unable to read the config at {:?}", path))?;
+
+    Config::from_str(&raw_config).with_context(|| format!("Unable to parse config at {:?}", path))
+  }
+
+  /// Configured fixed groups merged with the shared version groups, groups with a common member
+  /// become a single group
+  pub fn fixed_groups(&self) -> Vec<Vec<String>> {
+    let mut groups = self.fixed.clone();
+
+    for group in &self.shared_version {
+      let (overlapping, mut rest): (Vec<Vec<String>>, Vec<Vec<String>>) = groups
+        .into_iter()
+        .partition(|fixed| fixed.iter().any(|member| group.contains(member)));
+
+      let mut merged = overlapping.into_iter().flatten().collect::<Vec<String>>();
+
+      for member in group {
+        if !merged.contains(member) {
+          merged.push(member.clone());
+        }
+      }
+
+      rest.push(merged);
+      groups = rest;
+    }
+
+    groups
+  }
+
+  pub fn ignore_globs(&self) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in &self.ignore {
+      builder.add(Glob::new(pattern)?);
+    }
+
+    builder.build()
+  }
+
+  pub async fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+    fs::write(path, toml::to_string(self)?).await?;
+
+    Ok(())
+  }
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Config {
+      directory: [".changeset"].iter().collect(),
+      changelog: "CHANGELOG.md".to_owned(),
+      ignore: Vec::new(),
+      build_args: Vec::new(),
+      publish_args: Vec::new(),
+      dependents_bump: None,
+      dependents: DependentsPolicy::default(),
+      transitive_dependents: false,
+      initial_development: false,
+      stable: Vec::new(),
+      keep_build_metadata: false,
+      fixed: Vec::new(),
+      shared_version: Vec::new(),
+      linked: Vec::new(),
+      ignore_dev_cycles: true,
+      registry: Registry::default(),
+      publish_wait: PublishWait::default(),
+    }
+  }
+}
+
+impl FromStr for Config {
+  type Err = toml::de::Error;
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    toml::from_str(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn from_str_empty() {
+    let config = Config::from_str("");
+
+    assert!(config.is_ok());
+
+    assert_eq!(config.unwrap(), Config::default());
+  }
+
+  #[test]
+  fn from_str() {
+    let config = Config::from_str(
+      r#"
+directory = ".changes"
+changelog = "CHANGES.md"
+ignore = ["mol-examples"]
+build_args = ["--release"]
+publish_args = ["--allow-dirty"]
+dependents_bump = "minor"
+dependents = "out-of-range"
+transitive_dependents = true
+initial_development = true
+stable = ["mol-core"]
+keep_build_metadata = true
+fixed = [["mol", "mol-core"]]
+linked = [["mol-cargo", "mol-core"]]
+ignore_dev_cycles = false
+
+[registry]
+name = "my-registry"
+api = "https://my-registry.example.com"
+index = "sparse+https://my-registry.example.com/index/"
+
+[publish_wait]
+timeout = 60
+max_attempts = 10
+initial_delay = 500
+max_delay = 5000
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+      config,
+      Config {
+        directory: ".changes".into(),
+        changelog: "CHANGES.md".to_owned(),
+        ignore: vec!["mol-examples".to_owned()],
+        build_args: vec!["--release".to_owned()],
+        publish_args: vec!["--allow-dirty".to_owned()],
+        dependents_bump: Some("minor".to_owned()),
+        dependents: DependentsPolicy::OutOfRange,
+        transitive_dependents: true,
+        initial_development: true,
+        stable: vec!["mol-core".to_owned()],
+        keep_build_metadata: true,
+        fixed: vec![vec!["mol".to_owned(), "mol-core".to_owned()]],
+        shared_version: Vec::new(),
+        linked: vec![vec!["mol-cargo".to_owned(), "mol-core".to_owned()]],
+        ignore_dev_cycles: false,
+        registry: Registry {
+          name: Some("my-registry".to_owned()),
+          api: Some("https://my-registry.example.com".to_owned()),
+          index: Some("sparse+https://my-registry.example.com/index/".to_owned()),
+        },
+        publish_wait: PublishWait {
+          timeout: 60,
+          max_attempts: 10,
+          initial_delay: 500,
+          max_delay: 5000,
+        },
+      }
+    );
+  }
+
+  #[test]
+  fn publish_wait_backoff() {
+    let wait = PublishWait::default();
+
+    assert_eq!(wait.backoff(1), Duration::from_secs(1));
+    assert_eq!(wait.backoff(2), Duration::from_secs(2));
+    assert_eq!(wait.backoff(5), Duration::from_secs(16));
+    assert_eq!(wait.backoff(6), Duration::from_secs(30));
+    assert_eq!(wait.backoff(100), Duration::from_secs(30));
+  }
+
+  #[test]
+  fn publish_wait_delay() {
+    let wait = PublishWait::default();
+
+    for attempt in 1..10 {
+      let delay = wait.delay(attempt);
+
+      assert!(delay >= wait.backoff(attempt) / 2);
+      assert!(delay <= wait.backoff(attempt));
+    }
+  }
+
+  #[test]
+  fn registry_select() {
+    let registry = Registry {
+      name: Some("my-registry".to_owned()),
+      api: Some("https://my-registry.example.com".to_owned()),
+      index: Some("sparse+https://my-registry.example.com/index/".to_owned()),
+    };
+
+    assert_eq!(registry.select(None), registry);
+    assert_eq!(registry.select(Some("my-registry")), registry);
+    assert_eq!(
+      registry.select(Some("other-registry")),
+      Registry {
+        name: Some("other-registry".to_owned()),
+        api: None,
+        index: None,
+      }
+    );
+  }
+
+  #[test]
+  fn fixed_groups() {
+    let config = Config {
+      fixed: vec![
+        vec!["a".to_owned(), "b".to_owned()],
+        vec!["c".to_owned(), "d".to_owned()],
+        vec!["e".to_owned(), "f".to_owned()],
+      ],
+      shared_version: vec![
+        vec!["b".to_owned(), "c".to_owned(), "x".to_owned()],
+        vec!["y".to_owned(), "z".to_owned()],
+      ],
+      ..Default::default()
+    };
+
+    assert_eq!(
+      config.fixed_groups(),
+      vec![
+        vec!["e".to_owned(), "f".to_owned()],
+        vec![
+          "a".to_owned(),
+          "b".to_owned(),
+          "c".to_owned(),
+          "d".to_owned(),
+          "x".to_owned()
+        ],
+        vec!["y".to_owned(), "z".to_owned()],
+      ]
+    );
+    assert!(!toml::to_string(&config).unwrap().contains("shared_version"));
+  }
+
+  #[test]
+  fn ignore_globs() {
+    let config = Config {
+      ignore: vec!["*-examples".to_owned(), "mol-bench".to_owned()],
+      ..Default::default()
+    };
+
+    let ignore = config.ignore_globs().unwrap();
+
+    assert!(ignore.is_match("mol-examples"));
+    assert!(ignore.is_match("mol-bench"));
+    assert!(!ignore.is_match("mol-core"));
+  }
+
+  #[test]
+  fn to_str() {
+    let config = Config::default();
+
+    assert_eq!(
+      Config::from_str(&toml::to_string(&config).unwrap()).unwrap(),
+      config
+    );
+  }
+}
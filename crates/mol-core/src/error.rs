@@ -1,4 +1,6 @@
+use std::io;
 use std::marker::PhantomData;
+use std::path::PathBuf;
 
 use itertools::Itertools;
 use thiserror::Error;
@@ -24,9 +26,66 @@ impl<V: VersionEditor> From<&str> for VersionParseError<V> {
   }
 }
 
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Dependency cycle detected {}", .0.join(" -> "))]
+pub struct DependencyCycleError(pub Vec<String>);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid version \"{version}\", {reason}")]
+pub struct VersionBumpError {
+  pub version: String,
+  pub reason: String,
+}
+
+impl VersionBumpError {
+  pub fn new<R: ToString>(version: &str, reason: R) -> Self {
+    VersionBumpError {
+      version: version.to_owned(),
+      reason: reason.to_string(),
+    }
+  }
+}
+
+fn pretty_print_exit_code(code: &Option<i32>) -> String {
+  match code {
+    Some(code) => format!("exit code {}", code),
+    None => "no exit code (terminated by a signal)".to_owned(),
+  }
+}
+
+/// Failure of a package manager subprocess ie `cargo publish`
 #[derive(Debug, Error)]
-#[error("Version bump error")]
-pub struct VersionBumpError;
+pub enum CommandError {
+  #[error("Unable to run `{command}`, invalid working directory {cwd:?}")]
+  WorkingDirectory {
+    command: String,
+    cwd: PathBuf,
+    #[source]
+    source: io::Error,
+  },
+  #[error("Unable to start `{command}` in {cwd:?}")]
+  Spawn {
+    command: String,
+    cwd: PathBuf,
+    #[source]
+    source: io::Error,
+  },
+  #[error("Unable to read the stderr of `{command}` in {cwd:?}")]
+  Stderr {
+    command: String,
+    cwd: PathBuf,
+    #[source]
+    source: io::Error,
+  },
+  #[error("`{command}` in {cwd:?} failed with {}\n{stderr}", pretty_print_exit_code(.code))]
+  Failed {
+    command: String,
+    cwd: PathBuf,
+    code: Option<i32>,
+    /// Last lines of stderr, the whole output is already forwarded while the command runs
+    stderr: String,
+  },
+}
 
 fn pretty_print_version_incompatbility(rustc_ver: &str, core_ver: &str) -> String {
   let mut message = vec![];
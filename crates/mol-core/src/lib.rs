@@ -1,12 +1,16 @@
 pub mod bump;
+pub mod calver;
 pub mod changelog;
 pub mod changeset;
 pub mod changesets;
+pub mod config;
 pub mod error;
 pub mod explorer;
 pub mod package;
 pub mod package_manager;
+pub mod plan;
 pub mod plugin;
+pub mod pre_release;
 pub mod prelude;
 pub mod semantic;
 pub mod version;
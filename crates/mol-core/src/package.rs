@@ -1,16 +1,73 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
 use std::path::PathBuf;
 
 use itertools::Itertools;
 
+use crate::error::DependencyCycleError;
 use crate::version::{Version, Versioned};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DependencyKind {
+  Normal,
+  Build,
+  Dev,
+}
+
+impl fmt::Display for DependencyKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DependencyKind::Normal => write!(f, "normal"),
+      DependencyKind::Build => write!(f, "build"),
+      DependencyKind::Dev => write!(f, "dev"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+  pub name: String,
+  /// Version requirement of the dependency
+  pub version: String,
+  pub kind: DependencyKind,
+  /// Name the dependency is declared under when it is renamed
+  pub alias: Option<String>,
+}
+
+impl Dependency {
+  pub fn new<N: Into<String>, V: Into<String>>(name: N, version: V) -> Self {
+    Dependency {
+      name: name.into(),
+      version: version.into(),
+      kind: DependencyKind::Normal,
+      alias: None,
+    }
+  }
+
+  pub fn with_kind(mut self, kind: DependencyKind) -> Self {
+    self.kind = kind;
+    self
+  }
+
+  pub fn with_alias<A: Into<String>>(mut self, alias: A) -> Self {
+    self.alias = Some(alias.into());
+    self
+  }
+
+  /// Name the dependency is declared under in the manifest
+  pub fn key(&self) -> &str {
+    self.alias.as_deref().unwrap_or(&self.name)
+  }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Package<T: Versioned> {
   pub path: PathBuf,
   pub name: String,
   pub version: Version<T>,
-  pub dependencies: Vec<(String, String)>,
+  pub dependencies: Vec<Dependency>,
+  /// False for private packages that are versioned but never published
+  pub publish: bool,
 }
 
 pub trait AsPackageGraph<T: Versioned> {
@@ -21,70 +78,204 @@ pub trait AsPackageGraph<T: Versioned> {
 pub struct PackageGraph<'a, T: Versioned> {
   edges: Vec<(&'a str, &'a Package<T>)>,
   nodes: Vec<&'a Package<T>>,
+  ignore_dev_cycles: bool,
 }
 
 impl<'a, T> PackageGraph<'a, T>
 where
   T: Versioned,
 {
+  /// Dev-dependency edges are dropped when they are the only thing keeping packages in a cycle,
+  /// enabled by default, when disabled any cycle is an error
+  pub fn with_ignore_dev_cycles(mut self, ignore_dev_cycles: bool) -> Self {
+    self.ignore_dev_cycles = ignore_dev_cycles;
+    self
+  }
+
+  pub fn package(&self, name: &str) -> Option<&'a Package<T>> {
+    self
+      .nodes
+      .iter()
+      .find(|package| package.name == name)
+      .copied()
+  }
+
   pub fn child_changes(&self, name: &'a str) -> Vec<&'a Package<T>> {
     self
       .edges
       .iter()
       .filter(|(p_name, _)| &name == p_name)
       .map(|(_, package)| *package)
+      .unique_by(|package| package.name.as_str())
       .collect()
   }
 
-  fn stagger_scores(&self, scores: &mut HashMap<&'a str, isize>) {
-    for (edge, target) in &self.edges {
-      if let Some(value) = scores.get(&*target.name).copied() {
-        if let Some(score) = scores.get_mut(&*edge) {
-          *score += value;
+  /// The packages and every package that depends on them directly or transitively
+  pub fn transitive_dependents(&self, names: Vec<&'a str>) -> HashSet<&'a str> {
+    let mut dependents: HashSet<&'a str> = names.iter().copied().collect();
+    let mut pending = names;
+
+    while let Some(name) = pending.pop() {
+      for dependent in self.child_changes(name) {
+        if dependents.insert(dependent.name.as_str()) {
+          pending.push(dependent.name.as_str());
         }
       }
     }
+
+    dependents
   }
 
-  pub fn update_order(&self) -> Vec<&'a Package<T>> {
-    let name_map: HashMap<&str, &'a Package<T>> = self
-      .nodes
+  /// Names of the normal and build dependencies of the package that are part of the graph, the
+  /// dependencies needed to publish it
+  pub fn internal_dependencies(&self, package: &Package<T>) -> Vec<&'a str> {
+    self
+      .edges
       .iter()
-      .map(|package| (package.name.as_str(), *package))
-      .collect();
+      .filter(|(name, dependent)| dependent.name == package.name && *name != package.name)
+      .filter(|(name, dependent)| {
+        dependent
+          .dependencies
+          .iter()
+          .any(|dependency| dependency.name == *name && dependency.kind != DependencyKind::Dev)
+      })
+      .map(|(name, _)| *name)
+      .filter(|name| self.package(name).is_some())
+      .unique()
+      .collect()
+  }
+
+  /// Dependencies of the package in the graph that are not placed yet, `dropped` dev-dependency
+  /// edges (dependent, dependency) are skipped
+  fn pending_dependencies<'b>(
+    &self,
+    package: &'b Package<T>,
+    placed: &HashSet<&str>,
+    dropped: &HashSet<(&str, &str)>,
+  ) -> Vec<&'b str> {
+    package
+      .dependencies
+      .iter()
+      .filter(|dependency| {
+        dependency.kind != DependencyKind::Dev
+          || !dropped.contains(&(package.name.as_str(), dependency.name.as_str()))
+      })
+      .map(|dependency| dependency.name.as_str())
+      .filter(|name| {
+        *name != package.name && self.package(name).is_some() && !placed.contains(name)
+      })
+      .collect()
+  }
+
+  /// Follows pending dependencies from the first remaining package until a package repeats
+  fn find_cycle(
+    &self,
+    remaining: &[&'a Package<T>],
+    placed: &HashSet<&str>,
+    dropped: &HashSet<(&str, &str)>,
+  ) -> DependencyCycleError {
+    let mut path: Vec<&str> = Vec::new();
+    let mut current = remaining.first().map(|package| package.name.as_str());
+
+    while let Some(name) = current {
+      if let Some(start) = path.iter().position(|visited| *visited == name) {
+        return DependencyCycleError(
+          path[start..]
+            .iter()
+            .chain(Some(&name))
+            .map(|name| name.to_string())
+            .collect(),
+        );
+      }
+
+      path.push(name);
+
+      current = self.package(name).and_then(|package| {
+        self
+          .pending_dependencies(package, placed, dropped)
+          .into_iter()
+          .min()
+      });
+    }
 
-    let base = self
+    DependencyCycleError(path.iter().map(|name| name.to_string()).collect())
+  }
+
+  /// Edges of the cycle where the dependent only depends on the next package through
+  /// dev-dependencies
+  fn dev_edges(&self, cycle: &DependencyCycleError) -> Vec<(&'a str, &'a str)> {
+    cycle
+      .0
+      .iter()
+      .tuple_windows()
+      .filter_map(|(dependent, dependency)| {
+        let package = self.package(dependent)?;
+        let dependency = self.package(dependency)?;
+
+        package
+          .dependencies
+          .iter()
+          .filter(|edge| edge.name == dependency.name)
+          .all(|edge| edge.kind == DependencyKind::Dev)
+          .then_some((package.name.as_str(), dependency.name.as_str()))
+      })
+      .collect()
+  }
+
+  /// Packages grouped by dependency depth, packages only depend on packages from earlier levels so
+  /// packages within a level are independent of each other, every level is sorted by name
+  pub fn levels(&self) -> Result<Vec<Vec<&'a Package<T>>>, DependencyCycleError> {
+    let mut levels = Vec::new();
+    let mut placed: HashSet<&str> = HashSet::new();
+    // dev-dependency edges dropped to break cycles
+    let mut dropped: HashSet<(&str, &str)> = HashSet::new();
+    let mut remaining: Vec<&'a Package<T>> = self
       .nodes
       .iter()
-      .map(|package| (package.name.as_str(), 0))
+      .copied()
+      .sorted_by(|a, b| a.name.cmp(&b.name))
       .collect();
 
-    let mut scores: HashMap<&'a str, isize> =
-      self.edges.iter().fold(base, |mut acc, (parent, package)| {
-        let value = acc
-          .get(&*package.name)
-          .map(|val| val + 1)
-          .unwrap_or_else(|| 0);
-
-        if let Some(score) = acc.get_mut(parent) {
-          *score += value
+    while !remaining.is_empty() {
+      let level = remaining
+        .iter()
+        .copied()
+        .filter(|package| {
+          self
+            .pending_dependencies(package, &placed, &dropped)
+            .is_empty()
+        })
+        .collect::<Vec<&'a Package<T>>>();
+
+      if level.is_empty() {
+        let cycle = self.find_cycle(&remaining, &placed, &dropped);
+        let dev_edges = if self.ignore_dev_cycles {
+          self.dev_edges(&cycle)
         } else {
-          acc.insert(parent, value);
+          Vec::new()
+        };
+
+        if dev_edges.is_empty() {
+          return Err(cycle);
         }
 
-        acc
-      });
+        // only the dev edges of this cycle are dropped, the remaining packages are tried again
+        dropped.extend(dev_edges);
+        continue;
+      }
 
-    for _ in 0..self.nodes.len() {
-      self.stagger_scores(&mut scores);
+      placed.extend(level.iter().map(|package| package.name.as_str()));
+      remaining.retain(|package| !placed.contains(package.name.as_str()));
+      levels.push(level);
     }
 
-    scores
-      .into_iter()
-      .sorted_by_key(|(_, score)| -*score)
-      .filter(|(name, _)| name_map.contains_key(name))
-      .map(|(name, _)| name_map[name])
-      .collect()
+    Ok(levels)
+  }
+
+  /// Packages in dependency order, dependencies always come before their dependents and packages
+  /// of the same depth are ordered by name
+  pub fn update_order(&self) -> Result<Vec<&'a Package<T>>, DependencyCycleError> {
+    Ok(self.levels()?.into_iter().flatten().collect())
   }
 }
 
@@ -99,12 +290,16 @@ where
         package
           .dependencies
           .iter()
-          .map(|(dep, _)| (dep.as_str(), package)),
+          .map(|dependency| (dependency.name.as_str(), package)),
       );
       acc
     });
 
-    PackageGraph { edges, nodes }
+    PackageGraph {
+      edges,
+      nodes,
+      ignore_dev_cycles: true,
+    }
   }
 }
 
@@ -122,18 +317,21 @@ mod tests {
         name: "foo".to_owned(),
         version: "1.0.0".into(),
         dependencies: vec![],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "bar".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "baz".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
     ];
 
@@ -143,7 +341,8 @@ mod tests {
       graph,
       PackageGraph {
         nodes: vec![&packages[0], &packages[1], &packages[2]],
-        edges: vec![("foo", &packages[1]), ("foo", &packages[2])]
+        edges: vec![("foo", &packages[1]), ("foo", &packages[2])],
+        ignore_dev_cycles: true,
       }
     );
   }
@@ -156,24 +355,27 @@ mod tests {
         name: "foo".to_owned(),
         version: "1.0.0".into(),
         dependencies: vec![],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "bar".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "baz".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
     ];
 
     let graph = packages.as_package_graph();
 
-    let update_order = graph.update_order();
+    let update_order = graph.update_order().unwrap();
 
     assert_eq!(update_order[0], &packages[0]);
   }
@@ -186,35 +388,326 @@ mod tests {
         name: "pre_foo".to_owned(),
         version: "1.0.0".into(),
         dependencies: vec![],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "foo".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("pre_foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("pre_foo", "1")],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "bar".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "baz".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
     ];
 
     let graph = packages.as_package_graph();
 
-    let update_order = graph.update_order();
+    let update_order = graph.update_order().unwrap();
 
     let packages_ref: Vec<&Package<Semantic>> = packages.iter().collect();
     assert_eq!(update_order[..2], packages_ref[..2]);
   }
 
+  #[test]
+  fn update_order_stable() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        path: "".into(),
+        name: "qux".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "bar".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "foo".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+    ];
+
+    let graph = packages.as_package_graph();
+
+    assert_eq!(
+      graph.update_order().unwrap(),
+      vec![&packages[2], &packages[1], &packages[0]]
+    );
+  }
+
+  #[test]
+  fn update_order_cycle() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        path: "".into(),
+        name: "foo".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("baz", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "bar".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "baz".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("bar", "1").with_kind(DependencyKind::Dev)],
+        publish: true,
+      },
+    ];
+
+    let graph = packages.as_package_graph().with_ignore_dev_cycles(false);
+
+    let error = graph.update_order().unwrap_err();
+
+    assert_eq!(
+      error,
+      DependencyCycleError(vec![
+        "bar".to_owned(),
+        "foo".to_owned(),
+        "baz".to_owned(),
+        "bar".to_owned()
+      ])
+    );
+    assert_eq!(
+      error.to_string(),
+      "Dependency cycle detected bar -> foo -> baz -> bar"
+    );
+
+    let graph = packages.as_package_graph();
+
+    assert_eq!(
+      graph.update_order().unwrap(),
+      vec![&packages[2], &packages[0], &packages[1]]
+    );
+  }
+
+  #[test]
+  fn levels_dev_cycle() {
+    // only the dev edge a -> b closes a cycle, c's dev edge on d still orders c after d
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        path: "".into(),
+        name: "a".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("b", "1").with_kind(DependencyKind::Dev)],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "b".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("a", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "c".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("d", "1").with_kind(DependencyKind::Dev)],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "d".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("a", "1")],
+        publish: true,
+      },
+    ];
+
+    let graph = packages.as_package_graph();
+
+    assert_eq!(
+      graph.levels().unwrap(),
+      vec![
+        vec![&packages[0]],
+        vec![&packages[1], &packages[3]],
+        vec![&packages[2]]
+      ]
+    );
+  }
+
+  #[test]
+  fn levels_normal_cycle() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        path: "".into(),
+        name: "a".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("b", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "b".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("a", "1")],
+        publish: true,
+      },
+    ];
+
+    let graph = packages.as_package_graph();
+
+    assert_eq!(
+      graph.levels().unwrap_err(),
+      DependencyCycleError(vec!["a".to_owned(), "b".to_owned(), "a".to_owned()])
+    );
+  }
+
+  #[test]
+  fn levels() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        path: "".into(),
+        name: "pre_foo".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "foo".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("pre_foo", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "bar".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("foo", "1"), Dependency::new("serde", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "baz".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("pre_foo", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "qux".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+    ];
+
+    let graph = packages.as_package_graph();
+
+    assert_eq!(
+      graph.levels().unwrap(),
+      vec![
+        vec![&packages[0], &packages[4]],
+        vec![&packages[3], &packages[1]],
+        vec![&packages[2]],
+      ]
+    );
+  }
+
+  #[test]
+  fn transitive_dependents() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        path: "".into(),
+        name: "foo".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "bar".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "baz".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("bar", "1").with_kind(DependencyKind::Dev)],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "qux".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+    ];
+
+    let graph = packages.as_package_graph();
+
+    assert_eq!(
+      graph.transitive_dependents(vec!["foo"]),
+      ["foo", "bar", "baz"].into_iter().collect()
+    );
+  }
+
+  #[test]
+  fn internal_dependencies() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        path: "".into(),
+        name: "foo".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "bar".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        path: "".into(),
+        name: "baz".to_owned(),
+        version: "1.0.0".into(),
+        dependencies: vec![
+          Dependency::new("foo", "1").with_kind(DependencyKind::Build),
+          Dependency::new("bar", "1").with_kind(DependencyKind::Dev),
+          Dependency::new("serde", "1"),
+        ],
+        publish: true,
+      },
+    ];
+
+    let graph = packages.as_package_graph();
+
+    assert_eq!(graph.internal_dependencies(&packages[2]), vec!["foo"]);
+  }
+
   #[test]
   fn child_changes() {
     let packages = vec![
@@ -223,18 +716,21 @@ mod tests {
         name: "foo".to_owned(),
         version: "1.0.0".into(),
         dependencies: vec![],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "bar".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
       Package {
         path: "".into(),
         name: "baz".to_owned(),
         version: "1.0.0".into(),
-        dependencies: vec![("foo".to_owned(), "1".to_owned())],
+        dependencies: vec![Dependency::new("foo", "1")],
+        publish: true,
       },
     ];
 
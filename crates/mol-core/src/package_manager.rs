@@ -1,10 +1,20 @@
+use std::collections::HashMap;
 use std::path::Path;
 
 use async_trait::async_trait;
 
-use crate::package::Package;
+use crate::config::Registry;
+use crate::package::{Dependency, Package};
 use crate::version::Versioned;
 
+/// Whether a package version is in the registry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VersionCheck {
+  Published,
+  /// Not in the registry (yet), with what the registry responded ie the http status
+  Missing(String),
+}
+
 #[async_trait]
 pub trait PackageManager {
   type Metadata: Clone;
@@ -25,11 +35,21 @@ pub trait PackageManager {
     metadata: &Self::Metadata,
   ) -> anyhow::Result<Vec<Package<V>>>;
 
+  /// Groups of packages that share a single version in their manifests, they are released as
+  /// implicit fixed groups since writing the version of one member moves the others
+  async fn shared_version_groups<V: Versioned + Send + Sync + 'static>(
+    _packages: &[Package<V>],
+    _metadata: &Self::Metadata,
+  ) -> anyhow::Result<Vec<Vec<String>>> {
+    Ok(Vec::new())
+  }
+
   async fn check_version<V: Versioned + Send + Sync + 'static>(
     &self,
     package: &Package<V>,
+    registry: &Registry,
     metadata: &Self::Metadata,
-  ) -> anyhow::Result<bool>;
+  ) -> anyhow::Result<VersionCheck>;
 
   async fn run_build<T: AsRef<Path> + Send + Sync>(
     &self,
@@ -38,11 +58,21 @@ pub trait PackageManager {
     metadata: &Self::Metadata,
   ) -> anyhow::Result<()>;
 
+  /// Runs once in the root directory before any package is published
+  async fn prepare_publish<T: AsRef<Path> + Send + Sync>(
+    &self,
+    crate_path: T,
+    metadata: &Self::Metadata,
+  ) -> anyhow::Result<()>;
+
+  /// `concurrent` is set when other publishes run at the same time so no build state is shared
   async fn run_publish<T: AsRef<Path> + Send + Sync>(
     &self,
     crate_path: T,
     publish_args: Vec<String>,
+    registry: &Registry,
     dry_run: bool,
+    concurrent: bool,
     metadata: &Self::Metadata,
   ) -> anyhow::Result<()>;
 
@@ -56,8 +86,17 @@ pub trait PackageManager {
   async fn apply_dependency_version<T: AsRef<Path> + Send + Sync>(
     &self,
     crate_path: T,
-    name: &str,
+    dependency: &Dependency,
     version: &str,
     metadata: &Self::Metadata,
   ) -> anyhow::Result<()>;
+
+  /// Runs once every version of a release is applied, `versions` maps the updated packages to their
+  /// new versions
+  async fn update_lockfile<T: AsRef<Path> + Send + Sync>(
+    &self,
+    crate_path: T,
+    versions: &HashMap<&str, String>,
+    metadata: &Self::Metadata,
+  ) -> anyhow::Result<()>;
 }
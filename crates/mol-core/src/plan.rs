@@ -0,0 +1,631 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::bump::{Bump, ReleaseReason};
+use crate::changeset::Changeset;
+use crate::package::{Dependency, Package, PackageGraph};
+use crate::pre_release::PreRelease;
+use crate::version::{VersionEditor, VersionMod, Versioned};
+
+/// Version of the [`ReleasePlanReport`] schema, bumped only on breaking changes to the schema
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Serializable form of a [`ReleasePlan`], new fields may be added but existing fields are never
+/// renamed or removed without bumping [`REPORT_SCHEMA_VERSION`]
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ReleasePlanReport {
+  pub schema_version: u32,
+  /// Pre-release tag when in pre-release mode
+  pub pre_release: Option<String>,
+  /// Released packages in update order
+  pub packages: Vec<PackageReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PackageReport {
+  pub name: String,
+  /// Path of the package manifest
+  pub path: PathBuf,
+  pub version: String,
+  pub next_version: String,
+  /// Bump kind, ie "patch" / "minor" / "major"
+  pub bump: String,
+  /// Why the package is released, ie "changeset" / "dependent" / "fixed-group"
+  pub reason: String,
+  /// Private packages are versioned but never published
+  pub private: bool,
+  pub changesets: Vec<ChangesetReport>,
+  pub dependencies: Vec<DependencyReport>,
+  pub dependents: Vec<String>,
+  /// Other members of the linked group that share the highest bump
+  pub linked: Vec<String>,
+  /// Changelog that is updated, null when the package has no changesets
+  pub changelog: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChangesetReport {
+  pub id: String,
+  pub message: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DependencyReport {
+  pub name: String,
+  /// Dependency kind, ie "normal" / "build" / "dev"
+  pub kind: String,
+  /// Current version requirement
+  pub version: String,
+  /// Rewritten version requirement
+  pub next_version: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DependencyUpdate<'a> {
+  pub dependency: &'a Dependency,
+  pub next_version: String,
+}
+
+#[derive(Debug)]
+pub struct PackageRelease<'a, V: Versioned> {
+  pub package: &'a Package<V>,
+  pub version: VersionMod<V>,
+  pub next_version: String,
+  pub reason: ReleaseReason,
+  /// Changesets that mention the package, empty when the package is only bumped as a dependent or
+  /// with its fixed group
+  pub changesets: Vec<&'a Changeset<V>>,
+  pub dependencies: Vec<DependencyUpdate<'a>>,
+  /// Packages that get bumped because they depend on this package
+  pub dependents: Vec<&'a str>,
+  /// Other members of the linked group that share the highest bump
+  pub linked: Vec<&'a str>,
+}
+
+#[derive(Debug)]
+pub struct ReleasePlan<'a, V: Versioned> {
+  pub packages: Vec<PackageRelease<'a, V>>,
+}
+
+impl<'a, V: VersionEditor> ReleasePlan<'a, V> {
+  pub fn new(graph: &PackageGraph<'a, V>, bump: &'a Bump<V>) -> anyhow::Result<Self> {
+    Self::build(graph, bump, None)
+  }
+
+  /// While in pre-release the next version is computed from every changeset since entering the
+  /// pre-release (pre_release_bump) but only packages changed by `bump` are released
+  pub fn pre_release(
+    graph: &PackageGraph<'a, V>,
+    bump: &'a Bump<V>,
+    pre_release: &PreRelease,
+    pre_release_bump: &Bump<V>,
+  ) -> anyhow::Result<Self> {
+    Self::build(graph, bump, Some((pre_release, pre_release_bump)))
+  }
+
+  /// Highest version in the fixed group of the package so every member is released with the same
+  /// version even if their versions drifted
+  fn group_version<'b, F>(
+    graph: &PackageGraph<'a, V>,
+    bump: &Bump<V>,
+    package: &'a Package<V>,
+    version: F,
+  ) -> anyhow::Result<&'b str>
+  where
+    'a: 'b,
+    F: Fn(&'a Package<V>) -> &'b str,
+  {
+    let mut group_version = version(package);
+
+    if let Some(group) = bump.fixed_group(&package.name) {
+      for member in group.iter().filter_map(|name| graph.package(name)) {
+        let member_version = version(member);
+
+        if V::compare(member_version, group_version)
+          .with_context(|| format!("Failed comparing versions of package {}", member.name))?
+          == Ordering::Greater
+        {
+          group_version = member_version;
+        }
+      }
+    }
+
+    Ok(group_version)
+  }
+
+  fn build(
+    graph: &PackageGraph<'a, V>,
+    bump: &'a Bump<V>,
+    pre_release: Option<(&PreRelease, &Bump<V>)>,
+  ) -> anyhow::Result<Self> {
+    let mut packages = Vec::new();
+    let mut updated: HashMap<&str, String> = HashMap::new();
+
+    for package in graph.update_order()? {
+      let package_bump = bump.package(&package.name);
+
+      if let Some(update) = package_bump.version() {
+        let current_version =
+          Self::group_version(graph, bump, package, |member| &member.version.value)?;
+
+        let next_version = match pre_release {
+          Some((pre_release, pre_release_bump)) => {
+            let initial_version = Self::group_version(graph, bump, package, |member| {
+              pre_release
+                .initial_versions
+                .get(&member.name)
+                .unwrap_or(&member.version.value)
+            })?;
+
+            pre_release_bump
+              .package(&package.name)
+              .version()
+              .unwrap_or(update)
+              .apply_pre_release(initial_version, current_version, &pre_release.tag)
+          }
+          None => update.apply(current_version),
+        }
+        .with_context(|| format!("Failed updating package {}", package.name))?;
+
+        updated.insert(package.name.as_str(), next_version.clone());
+
+        let dependencies = package
+          .dependencies
+          .iter()
+          .filter(|dependency| updated.contains_key(dependency.name.as_str()))
+          .filter(|dependency| !V::r#match(&dependency.version, &updated[dependency.name.as_str()]))
+          .map(|dependency| DependencyUpdate {
+            dependency,
+            next_version: V::mask(&dependency.version, &updated[dependency.name.as_str()]),
+          })
+          .collect();
+
+        packages.push(PackageRelease {
+          package,
+          version: update.clone(),
+          next_version,
+          reason: package_bump.reason().unwrap_or(ReleaseReason::Dependent),
+          changesets: package_bump.changesets().unwrap_or_default(),
+          dependencies,
+          dependents: graph
+            .child_changes(&package.name)
+            .into_iter()
+            .filter(|dependent| bump.package(&dependent.name).version().is_some())
+            .map(|dependent| dependent.name.as_str())
+            .collect(),
+          linked: bump
+            .linked_group(&package.name)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|member| *member != package.name)
+            .collect(),
+        });
+      }
+    }
+
+    Ok(ReleasePlan { packages })
+  }
+
+  /// Next versions keep the build metadata of the current versions, dependency requirements never
+  /// carry build metadata so they are left as is
+  pub fn with_keep_build_metadata(mut self, keep_build_metadata: bool) -> anyhow::Result<Self> {
+    if keep_build_metadata {
+      for release in &mut self.packages {
+        release.next_version =
+          V::with_build_metadata(&release.next_version, &release.package.version.value)
+            .with_context(|| format!("Failed updating package {}", release.package.name))?;
+      }
+    }
+
+    Ok(self)
+  }
+
+  /// Snapshot of the plan, released packages and all of their transitive dependents (bumped like
+  /// dependents when the plan doesn't release them) get a snapshot of their next version and every
+  /// requirement on a snapshot is pinned, a plain requirement never matches a pre-release
+  pub fn with_snapshot(self, graph: &PackageGraph<'a, V>, snapshot: &str) -> anyhow::Result<Self> {
+    let snapshotted = graph.transitive_dependents(
+      self
+        .packages
+        .iter()
+        .map(|release| release.package.name.as_str())
+        .collect(),
+    );
+
+    let mut releases = self.packages;
+    let mut packages = Vec::new();
+    let mut updated: HashMap<&str, String> = HashMap::new();
+
+    for package in graph.update_order()? {
+      if !snapshotted.contains(package.name.as_str()) {
+        continue;
+      }
+
+      let mut release = match releases
+        .iter()
+        .position(|release| release.package.name == package.name)
+      {
+        Some(index) => releases.remove(index),
+        None => {
+          let version = VersionMod::new(V::options().into_iter().min().unwrap_or_default());
+
+          PackageRelease {
+            package,
+            next_version: version
+              .apply(&package.version.value)
+              .with_context(|| format!("Failed updating package {}", package.name))?,
+            version,
+            reason: ReleaseReason::Dependent,
+            changesets: Vec::new(),
+            dependencies: Vec::new(),
+            dependents: Vec::new(),
+            linked: Vec::new(),
+          }
+        }
+      };
+
+      release.next_version = V::snapshot(&release.next_version, snapshot)
+        .with_context(|| format!("Failed updating package {}", package.name))?;
+      updated.insert(package.name.as_str(), release.next_version.clone());
+
+      packages.push(release);
+    }
+
+    for release in &mut packages {
+      release.dependencies = release
+        .package
+        .dependencies
+        .iter()
+        .filter(|dependency| updated.contains_key(dependency.name.as_str()))
+        .map(|dependency| DependencyUpdate {
+          dependency,
+          next_version: format!("={}", updated[dependency.name.as_str()]),
+        })
+        .collect();
+      release.dependents = graph
+        .child_changes(&release.package.name)
+        .into_iter()
+        .map(|dependent| dependent.name.as_str())
+        .collect();
+    }
+
+    Ok(ReleasePlan { packages })
+  }
+
+  pub fn package(&self, name: &str) -> Option<&PackageRelease<'a, V>> {
+    self
+      .packages
+      .iter()
+      .find(|release| release.package.name == name)
+  }
+
+  pub fn report(&self, changelog: &str, pre_release: Option<&PreRelease>) -> ReleasePlanReport {
+    ReleasePlanReport {
+      schema_version: REPORT_SCHEMA_VERSION,
+      pre_release: pre_release.map(|pre_release| pre_release.tag.clone()),
+      packages: self
+        .packages
+        .iter()
+        .map(|release| PackageReport {
+          name: release.package.name.clone(),
+          path: release.package.path.clone(),
+          version: release.package.version.value.clone(),
+          next_version: release.next_version.clone(),
+          bump: release.version.to_string(),
+          reason: release.reason.to_string(),
+          private: !release.package.publish,
+          changesets: release
+            .changesets
+            .iter()
+            .map(|changeset| ChangesetReport {
+              id: changeset.id.clone(),
+              message: changeset.message.clone(),
+            })
+            .collect(),
+          dependencies: release
+            .dependencies
+            .iter()
+            .map(|update| DependencyReport {
+              name: update.dependency.name.clone(),
+              kind: update.dependency.kind.to_string(),
+              version: update.dependency.version.clone(),
+              next_version: update.next_version.clone(),
+            })
+            .collect(),
+          dependents: release
+            .dependents
+            .iter()
+            .map(|dependent| dependent.to_string())
+            .collect(),
+          linked: release
+            .linked
+            .iter()
+            .map(|member| member.to_string())
+            .collect(),
+          changelog: release
+            .package
+            .path
+            .parent()
+            .filter(|_| !release.changesets.is_empty())
+            .map(|root_path| root_path.join(changelog)),
+        })
+        .collect(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::prelude::*;
+
+  fn packages() -> Vec<Package<Semantic>> {
+    vec![
+      Package {
+        name: "mol-core".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "mol".to_owned(),
+        path: "".into(),
+        version: "0.1.0".into(),
+        dependencies: vec![Dependency::new("mol-core", "0.1")],
+        publish: true,
+      },
+    ]
+  }
+
+  fn changeset(name: &str, version: Semantic) -> Changeset<Semantic> {
+    Changeset {
+      id: "dolor-quo".to_owned(),
+      packages: vec![(name.to_owned(), VersionMod::new(version))]
+        .into_iter()
+        .collect(),
+      message: "Too bad we dont play games".to_owned(),
+    }
+  }
+
+  #[test]
+  fn new() {
+    let packages = packages();
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default();
+    bump.add(changeset("mol-core", Semantic::minor()), &graph);
+
+    let plan = ReleasePlan::new(&graph, &bump).unwrap();
+
+    let core = plan.package("mol-core").unwrap();
+
+    assert_eq!(core.version, VersionMod::new(Semantic::minor()));
+    assert_eq!(core.next_version, "0.2.0");
+    assert_eq!(core.changesets.len(), 1);
+    assert_eq!(core.changesets[0].id, "dolor-quo");
+    assert_eq!(core.dependents, vec!["mol"]);
+
+    let mol = plan.package("mol").unwrap();
+
+    assert_eq!(mol.version, VersionMod::new(Semantic::patch()));
+    assert_eq!(mol.next_version, "0.1.1");
+    assert!(mol.changesets.is_empty());
+    assert_eq!(
+      mol.dependencies,
+      vec![DependencyUpdate {
+        dependency: &Dependency::new("mol-core", "0.1"),
+        next_version: "0.2".to_owned()
+      }]
+    );
+  }
+
+  #[test]
+  fn fixed() {
+    let mut packages = packages();
+    packages[0].version = "0.2.3".into();
+    packages[1].dependencies = vec![];
+
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default().with_fixed(vec![vec!["mol".to_owned(), "mol-core".to_owned()]]);
+    bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    let plan = ReleasePlan::new(&graph, &bump).unwrap();
+
+    assert_eq!(plan.package("mol-core").unwrap().next_version, "0.2.4");
+    assert_eq!(plan.package("mol").unwrap().next_version, "0.2.4");
+    assert_eq!(
+      plan.package("mol").unwrap().reason,
+      ReleaseReason::FixedGroup
+    );
+  }
+
+  #[test]
+  fn initial_development() {
+    let mut packages = packages();
+    packages[0].version = "0.3.2".into();
+    packages[1].dependencies = vec![Dependency::new("mol-core", "^0.3")];
+
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default()
+      .with_dependents(DependentsPolicy::OutOfRange)
+      .with_initial_development(true, vec![]);
+    bump.add(changeset("mol-core", Semantic::major()), &graph);
+
+    let plan = ReleasePlan::new(&graph, &bump).unwrap();
+
+    let core = plan.package("mol-core").unwrap();
+
+    assert_eq!(core.version, VersionMod::new(Semantic::minor()));
+    assert_eq!(core.next_version, "0.4.0");
+    assert_eq!(core.dependents, vec!["mol"]);
+
+    let mol = plan.package("mol").unwrap();
+
+    assert_eq!(mol.next_version, "0.1.1");
+    assert_eq!(
+      mol.dependencies,
+      vec![DependencyUpdate {
+        dependency: &Dependency::new("mol-core", "^0.3"),
+        next_version: "^0.4".to_owned()
+      }]
+    );
+  }
+
+  #[test]
+  fn keep_build_metadata() {
+    let mut packages = packages();
+    packages[0].version = "1.2.0+build5".into();
+
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default();
+    bump.add(changeset("mol-core", Semantic::minor()), &graph);
+
+    let plan = ReleasePlan::new(&graph, &bump)
+      .unwrap()
+      .with_keep_build_metadata(true)
+      .unwrap();
+
+    assert_eq!(
+      plan.package("mol-core").unwrap().next_version,
+      "1.3.0+build5"
+    );
+    assert_eq!(plan.package("mol").unwrap().next_version, "0.1.1");
+    assert_eq!(
+      plan.package("mol").unwrap().dependencies[0].next_version,
+      "1.3"
+    );
+
+    let plan = ReleasePlan::new(&graph, &bump)
+      .unwrap()
+      .with_keep_build_metadata(false)
+      .unwrap();
+
+    assert_eq!(plan.package("mol-core").unwrap().next_version, "1.3.0");
+  }
+
+  #[test]
+  fn report() {
+    let mut packages = packages();
+    packages[0].path = "crates/mol-core/Cargo.toml".into();
+
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default();
+    bump.add(changeset("mol-core", Semantic::minor()), &graph);
+
+    let report = ReleasePlan::new(&graph, &bump)
+      .unwrap()
+      .report("CHANGELOG.md", None);
+
+    assert_eq!(report.schema_version, REPORT_SCHEMA_VERSION);
+    assert_eq!(report.pre_release, None);
+    assert_eq!(
+      report.packages[0],
+      PackageReport {
+        name: "mol-core".to_owned(),
+        path: "crates/mol-core/Cargo.toml".into(),
+        version: "0.1.0".to_owned(),
+        next_version: "0.2.0".to_owned(),
+        bump: "minor".to_owned(),
+        reason: "changeset".to_owned(),
+        private: false,
+        changesets: vec![ChangesetReport {
+          id: "dolor-quo".to_owned(),
+          message: "Too bad we dont play games".to_owned(),
+        }],
+        dependencies: vec![],
+        dependents: vec!["mol".to_owned()],
+        linked: vec![],
+        changelog: Some("crates/mol-core/CHANGELOG.md".into()),
+      }
+    );
+    assert_eq!(report.packages[1].reason, "dependent");
+    assert_eq!(report.packages[1].changelog, None);
+  }
+
+  #[test]
+  fn pre_release() {
+    let mut packages = packages();
+    packages[0].version = "0.2.0-beta.0".into();
+
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default();
+    bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    let mut pre_release_bump = Bump::default();
+    pre_release_bump.add(changeset("mol-core", Semantic::minor()), &graph);
+    pre_release_bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    let pre_release = PreRelease::new(
+      "beta".to_owned(),
+      vec![("mol-core".to_owned(), "0.1.0".to_owned())]
+        .into_iter()
+        .collect(),
+    );
+
+    let plan = ReleasePlan::pre_release(&graph, &bump, &pre_release, &pre_release_bump).unwrap();
+
+    assert_eq!(
+      plan.package("mol-core").unwrap().next_version,
+      "0.2.0-beta.1"
+    );
+    assert_eq!(plan.package("mol").unwrap().next_version, "0.1.1-beta.0");
+  }
+
+  #[test]
+  fn snapshot() {
+    let mut packages = packages();
+    packages.push(Package {
+      name: "mol-cli".to_owned(),
+      path: "".into(),
+      version: "0.1.0".into(),
+      dependencies: vec![Dependency::new("mol", "0.1.0")],
+      publish: true,
+    });
+
+    let graph = packages.as_package_graph();
+
+    let mut bump = Bump::default().with_dependents(DependentsPolicy::OutOfRange);
+    bump.add(changeset("mol-core", Semantic::patch()), &graph);
+
+    let plan = ReleasePlan::new(&graph, &bump)
+      .unwrap()
+      .with_snapshot(&graph, "snapshot-20261018123045")
+      .unwrap();
+
+    assert_eq!(
+      plan
+        .packages
+        .iter()
+        .map(|release| (release.package.name.as_str(), release.next_version.as_str()))
+        .collect::<Vec<_>>(),
+      vec![
+        ("mol-core", "0.1.1-snapshot-20261018123045"),
+        ("mol", "0.1.1-snapshot-20261018123045"),
+        ("mol-cli", "0.1.1-snapshot-20261018123045"),
+      ]
+    );
+    assert_eq!(
+      plan.package("mol-cli").unwrap().dependencies,
+      vec![DependencyUpdate {
+        dependency: &Dependency::new("mol", "0.1.0"),
+        next_version: "=0.1.1-snapshot-20261018123045".to_owned(),
+      }]
+    );
+    assert_eq!(
+      plan.package("mol").unwrap().dependencies[0].next_version,
+      "=0.1.1-snapshot-20261018123045"
+    );
+  }
+}
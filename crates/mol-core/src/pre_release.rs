@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::package::{Package, PackageGraph};
+use crate::plan::DependencyUpdate;
+use crate::version::{VersionEditor, Versioned};
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PreRelease {
+  /// Pre-release tag, ie "beta" for 1.2.0-beta.0
+  pub tag: String,
+  /// Changeset files already released as part of this pre-release
+  pub changesets: BTreeSet<String>,
+  /// Versions of the packages when the pre-release mode was entered
+  pub initial_versions: BTreeMap<String, String>,
+  /// Requirements of the packages on each other when the pre-release mode was entered, by
+  /// dependent and dependency, graduated requirements are written with their precision
+  #[serde(default)]
+  pub initial_requirements: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Changes `pre exit` makes to a package
+#[derive(Debug, PartialEq)]
+pub struct PreReleaseExit<'a, V: Versioned> {
+  pub package: &'a Package<V>,
+  /// Stable version, none when the package was not pre-released since entering
+  pub next_version: Option<String>,
+  pub dependencies: Vec<DependencyUpdate<'a>>,
+}
+
+impl PreRelease {
+  pub fn new(tag: String, initial_versions: BTreeMap<String, String>) -> Self {
+    PreRelease {
+      tag,
+      initial_versions,
+      ..Default::default()
+    }
+  }
+
+  pub async fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Option<Self>> {
+    let path = path.as_ref();
+
+    if !path.exists() {
+      return Ok(None);
+    }
+
+    let raw_pre_release = fs::read_to_string(path)
+      .await
+      .with_context(|| format!("Unable to read the pre-release state at {:?}", path))?;
+
+    PreRelease::from_str(&raw_pre_release)
+      .map(Some)
+      .with_context(|| format!("Unable to parse the pre-release state at {:?}", path))
+  }
+
+  pub async fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+    fs::write(path, toml::to_string(self)?).await?;
+
+    Ok(())
+  }
+
+  pub fn contains<P: AsRef<Path>>(&self, changeset_path: P) -> bool {
+    changeset_path
+      .as_ref()
+      .file_name()
+      .and_then(|name| name.to_str())
+      .map(|name| self.changesets.contains(name))
+      .unwrap_or(false)
+  }
+
+  pub fn insert<P: AsRef<Path>>(&mut self, changeset_path: P) {
+    if let Some(name) = changeset_path
+      .as_ref()
+      .file_name()
+      .and_then(|name| name.to_str())
+    {
+      self.changesets.insert(name.to_owned());
+    }
+  }
+
+  /// Stable versions of the packages pre-released since entering in update order, packages that
+  /// already were pre-releases when entering keep their version while requirements on graduated
+  /// packages are rewritten in every package
+  pub fn exit<'a, V: VersionEditor>(
+    &self,
+    graph: &PackageGraph<'a, V>,
+  ) -> anyhow::Result<Vec<PreReleaseExit<'a, V>>> {
+    let mut exits = Vec::new();
+    let mut updated: HashMap<&str, String> = HashMap::new();
+
+    for package in graph.update_order()? {
+      let next_version = match self.initial_versions.get(&package.name) {
+        Some(initial_version) if initial_version != &package.version.value => Some(
+          V::release(&package.version.value)
+            .with_context(|| format!("Failed releasing package {}", package.name))?,
+        ),
+        _ => None,
+      };
+
+      if let Some(next_version) = &next_version {
+        updated.insert(package.name.as_str(), next_version.clone());
+      }
+
+      let dependencies: Vec<_> = package
+        .dependencies
+        .iter()
+        .filter(|dependency| updated.contains_key(dependency.name.as_str()))
+        .filter(|dependency| !V::r#match(&dependency.version, &updated[dependency.name.as_str()]))
+        .map(|dependency| DependencyUpdate {
+          dependency,
+          next_version: V::rewrite(
+            self
+              .initial_requirements
+              .get(&package.name)
+              .and_then(|requirements| requirements.get(&dependency.name))
+              .unwrap_or(&dependency.version),
+            &updated[dependency.name.as_str()],
+          ),
+        })
+        .collect();
+
+      if next_version.is_some() || !dependencies.is_empty() {
+        exits.push(PreReleaseExit {
+          package,
+          next_version,
+          dependencies,
+        });
+      }
+    }
+
+    Ok(exits)
+  }
+}
+
+impl FromStr for PreRelease {
+  type Err = toml::de::Error;
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    toml::from_str(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::package::{AsPackageGraph, Dependency};
+  use crate::semantic::Semantic;
+  use crate::version::VersionEditor;
+
+  #[test]
+  fn from_str() {
+    let pre_release = PreRelease::from_str(
+      r#"
+tag = "beta"
+changesets = ["dolor-quo.md"]
+
+[initial_versions]
+mol = "0.3.0"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(pre_release.tag, "beta");
+    assert!(pre_release.contains(".changeset/dolor-quo.md"));
+    assert!(!pre_release.contains(".changeset/et-rerum.md"));
+    assert_eq!(
+      pre_release.initial_versions.get("mol"),
+      Some(&"0.3.0".to_owned())
+    );
+  }
+
+  #[test]
+  fn to_str() {
+    let mut pre_release = PreRelease::new(
+      "beta".to_owned(),
+      vec![("mol".to_owned(), "0.3.0".to_owned())]
+        .into_iter()
+        .collect(),
+    );
+
+    pre_release.insert(".changeset/dolor-quo.md");
+
+    assert_eq!(
+      PreRelease::from_str(&toml::to_string(&pre_release).unwrap()).unwrap(),
+      pre_release
+    );
+  }
+
+  #[test]
+  fn exit() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        name: "mol-core".to_owned(),
+        path: "".into(),
+        version: "0.4.0-beta.1".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "mol-cargo".to_owned(),
+        path: "".into(),
+        version: "0.2.0-alpha.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "mol".to_owned(),
+        path: "".into(),
+        version: "0.3.0".into(),
+        dependencies: vec![
+          Dependency::new("mol-core", "0.4.0-beta.1"),
+          Dependency::new("mol-cargo", "0.2.0-alpha.0"),
+        ],
+        publish: true,
+      },
+    ];
+    let graph = packages.as_package_graph();
+
+    let pre_release = PreRelease::new(
+      "beta".to_owned(),
+      vec![
+        ("mol-core".to_owned(), "0.3.0".to_owned()),
+        ("mol-cargo".to_owned(), "0.2.0-alpha.0".to_owned()),
+        ("mol".to_owned(), "0.3.0".to_owned()),
+      ]
+      .into_iter()
+      .collect(),
+    );
+
+    let exits = pre_release.exit(&graph).unwrap();
+
+    assert_eq!(
+      exits,
+      vec![
+        PreReleaseExit {
+          package: &packages[0],
+          next_version: Some("0.4.0".to_owned()),
+          dependencies: vec![],
+        },
+        PreReleaseExit {
+          package: &packages[2],
+          next_version: None,
+          dependencies: vec![DependencyUpdate {
+            dependency: &packages[2].dependencies[0],
+            next_version: "0.4.0".to_owned(),
+          }],
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn exit_initial_requirements() {
+    let mut packages: Vec<Package<Semantic>> = vec![
+      Package {
+        name: "mol-core".to_owned(),
+        path: "".into(),
+        version: "1.2.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "mol".to_owned(),
+        path: "".into(),
+        version: "1.0.0".into(),
+        dependencies: vec![Dependency::new("mol-core", "1.2")],
+        publish: true,
+      },
+    ];
+
+    let mut pre_release = PreRelease::new(
+      "beta".to_owned(),
+      vec![
+        ("mol-core".to_owned(), "1.2.0".to_owned()),
+        ("mol".to_owned(), "1.0.0".to_owned()),
+      ]
+      .into_iter()
+      .collect(),
+    );
+    pre_release.initial_requirements.insert(
+      "mol".to_owned(),
+      vec![("mol-core".to_owned(), "1.2".to_owned())]
+        .into_iter()
+        .collect(),
+    );
+
+    // `mol version` in pre-release writes the requirement at full precision
+    packages[0].version = "1.3.0-beta.0".into();
+    packages[1].version = "1.0.1-beta.0".into();
+    packages[1].dependencies = vec![Dependency::new(
+      "mol-core",
+      Semantic::mask("1.2", "1.3.0-beta.0"),
+    )];
+    assert_eq!(packages[1].dependencies[0].version, "1.3.0-beta.0");
+
+    let graph = packages.as_package_graph();
+
+    let exits = pre_release.exit(&graph).unwrap();
+
+    assert_eq!(exits[1].next_version, Some("1.0.1".to_owned()));
+    assert_eq!(
+      exits[1].dependencies,
+      vec![DependencyUpdate {
+        dependency: &packages[1].dependencies[0],
+        next_version: "1.3".to_owned(),
+      }]
+    );
+  }
+}
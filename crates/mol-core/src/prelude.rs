@@ -1,12 +1,21 @@
-pub use crate::bump::Bump;
+pub use crate::bump::{Bump, ReleaseReason};
+pub use crate::calver::{CalVer, CalVerScheme, YearMonthMicro};
 pub use crate::changelog::{AsChangelogFmt, Changelog};
 pub use crate::changeset::Changeset;
 pub use crate::changesets::Changesets;
-pub use crate::error::{ChangesetParseError, VersionParseError};
+pub use crate::config::{Config, DependentsPolicy, PublishWait, Registry};
+pub use crate::error::{
+  ChangesetParseError, CommandError, DependencyCycleError, VersionBumpError, VersionParseError,
+};
 pub use crate::explorer::Explorer;
-pub use crate::package::{AsPackageGraph, Package, PackageGraph};
-pub use crate::package_manager::PackageManager;
+pub use crate::package::{AsPackageGraph, Dependency, DependencyKind, Package, PackageGraph};
+pub use crate::package_manager::{PackageManager, VersionCheck};
+pub use crate::plan::{
+  ChangesetReport, DependencyReport, DependencyUpdate, PackageRelease, PackageReport, ReleasePlan,
+  ReleasePlanReport,
+};
 pub use crate::plugin::{Plugin, PluginContext, PluginManager, PluginProxy, PluginRegistrar};
-pub use crate::semantic::Semantic;
+pub use crate::pre_release::{PreRelease, PreReleaseExit};
+pub use crate::semantic::{Semantic, SemanticValue};
 pub use crate::version::{Version, VersionEditor, VersionMod, Versioned};
 pub use crate::DEFAULT_PACKAGE_DIR;
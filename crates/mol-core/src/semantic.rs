@@ -1,7 +1,11 @@
+use std::cmp::Ordering;
+use std::fmt;
 use std::str::FromStr;
 
+use semver::{Comparator, Op, VersionReq};
+
 use crate::error::{VersionBumpError, VersionParseError};
-use crate::version::{VersionEditor, Versioned};
+use crate::version::{Version, VersionEditor, Versioned};
 
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd)]
 enum SemanticVersion {
@@ -33,45 +37,276 @@ impl Semantic {
   }
 }
 
+/// Parsed semver 2.0 version, ordered by precedence with build metadata only breaking ties so the
+/// ordering agrees with equality, see [`SemanticValue::precedence`] to ignore build metadata
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SemanticValue(semver::Version);
+
+impl SemanticValue {
+  pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+    SemanticValue(semver::Version::new(major, minor, patch))
+  }
+
+  pub fn major(&self) -> u64 {
+    self.0.major
+  }
+
+  pub fn minor(&self) -> u64 {
+    self.0.minor
+  }
+
+  pub fn patch(&self) -> u64 {
+    self.0.patch
+  }
+
+  pub fn pre_release(&self) -> Option<&str> {
+    Some(self.0.pre.as_str()).filter(|pre| !pre.is_empty())
+  }
+
+  pub fn build_metadata(&self) -> Option<&str> {
+    Some(self.0.build.as_str()).filter(|build| !build.is_empty())
+  }
+
+  /// The version without pre-release and build metadata
+  pub fn release(&self) -> Self {
+    Self::new(self.0.major, self.0.minor, self.0.patch)
+  }
+
+  /// Copy of the version with the build metadata of `other`
+  pub fn with_build_metadata_of(&self, other: &SemanticValue) -> Self {
+    let mut value = self.0.clone();
+    value.build = other.0.build.clone();
+    SemanticValue(value)
+  }
+
+  /// Precedence by the spec, pre-release identifiers are compared numerically when both are
+  /// numeric and build metadata doesn't count
+  pub fn precedence(&self, other: &SemanticValue) -> Ordering {
+    (self.0.major, self.0.minor, self.0.patch, &self.0.pre).cmp(&(
+      other.0.major,
+      other.0.minor,
+      other.0.patch,
+      &other.0.pre,
+    ))
+  }
+}
+
+impl FromStr for SemanticValue {
+  type Err = VersionBumpError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    semver::Version::parse(value)
+      .map(SemanticValue)
+      .map_err(|err| VersionBumpError::new(value, err))
+  }
+}
+
+impl fmt::Display for SemanticValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl Version<Semantic> {
+  /// Parsed value of the version
+  pub fn semantic(&self) -> Result<SemanticValue, VersionBumpError> {
+    self.value.parse()
+  }
+}
+
+/// Number of version components written in a comparator, ie 2 for `^1.2`
+fn precision(comparator: &Comparator) -> usize {
+  match (comparator.minor, comparator.patch) {
+    (None, _) => 1,
+    (Some(_), None) => 2,
+    (Some(_), Some(_)) => 3,
+  }
+}
+
+fn components(version: &semver::Version, precision: usize) -> String {
+  if !version.pre.is_empty() {
+    return format!(
+      "{}.{}.{}-{}",
+      version.major, version.minor, version.patch, version.pre
+    );
+  }
+
+  [version.major, version.minor, version.patch][..precision]
+    .iter()
+    .map(u64::to_string)
+    .collect::<Vec<String>>()
+    .join(".")
+}
+
+/// Rewrites a single comparator of a requirement for `version`, the operator as written (implicit
+/// carets stay implicit) and the precision are kept
+fn mask_comparator(text: &str, version: &semver::Version) -> String {
+  let text = text.trim();
+
+  let comparator = match Comparator::from_str(text) {
+    Ok(comparator) => comparator,
+    // `*` can't be parsed as a single comparator and already matches every release
+    Err(_) => return text.to_owned(),
+  };
+
+  let operator = &text[..text
+    .find(|c: char| c.is_ascii_digit())
+    .unwrap_or(text.len())];
+  let precision = precision(&comparator);
+
+  match comparator.op {
+    Op::Exact | Op::Caret | Op::Tilde | Op::GreaterEq | Op::LessEq => {
+      format!("{}{}", operator, components(version, precision))
+    }
+    // an exclusive upper bound moves past the new version by its last written component
+    Op::Less => {
+      let mut bound = [version.major, version.minor, version.patch];
+      bound[precision - 1] += 1;
+
+      let bound = bound[..precision]
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<String>>()
+        .join(".");
+
+      format!("{}{}", operator, bound)
+    }
+    Op::Wildcard if version.pre.is_empty() => format!("{}.*", components(version, precision)),
+    Op::Wildcard => components(version, precision),
+    _ => text.to_owned(),
+  }
+}
+
 impl Versioned for Semantic {
   fn apply(&self, current: &str) -> Result<String, VersionBumpError> {
-    let mut current = current.split('.');
-
-    let major = current
-      .next()
-      .and_then(|val| val.parse::<i32>().ok())
-      .ok_or(VersionBumpError)?;
-    let minor = current
-      .next()
-      .and_then(|val| val.parse::<i32>().ok())
-      .ok_or(VersionBumpError)?;
-    // TODO: Allow dev builds and not
-    let patch = current
-      .next()
-      .and_then(|val| val.parse::<i32>().ok())
-      .ok_or(VersionBumpError)?;
-
-    Ok(match self.r#type {
-      SemanticVersion::Major => format!("{}.{}.{}", major + 1, 0, 0),
-      SemanticVersion::Minor => format!("{}.{}.{}", major, minor + 1, 0),
-      SemanticVersion::Patch => format!("{}.{}.{}", major, minor, patch + 1),
-    })
+    let current = current.parse::<SemanticValue>()?;
+    let (major, minor, patch) = (current.major(), current.minor(), current.patch());
+
+    // A pre-release already precedes the release it's bumping towards
+    let is_pre_release = current.pre_release().is_some();
+
+    Ok(
+      match self.r#type {
+        SemanticVersion::Major if is_pre_release && minor == 0 && patch == 0 => {
+          SemanticValue::new(major, 0, 0)
+        }
+        SemanticVersion::Minor if is_pre_release && patch == 0 => {
+          SemanticValue::new(major, minor, 0)
+        }
+        SemanticVersion::Patch if is_pre_release => SemanticValue::new(major, minor, patch),
+        SemanticVersion::Major => SemanticValue::new(major + 1, 0, 0),
+        SemanticVersion::Minor => SemanticValue::new(major, minor + 1, 0),
+        SemanticVersion::Patch => SemanticValue::new(major, minor, patch + 1),
+      }
+      .to_string(),
+    )
+  }
+
+  fn apply_pre_release(
+    &self,
+    initial: &str,
+    current: &str,
+    tag: &str,
+  ) -> Result<String, VersionBumpError> {
+    let target = self.apply(initial)?;
+
+    let current = current.parse::<SemanticValue>()?;
+
+    let next_pre = current
+      .pre_release()
+      .filter(|_| current.release().to_string() == target)
+      .and_then(|pre_release| pre_release.strip_prefix(tag))
+      .and_then(|counter| counter.strip_prefix('.'))
+      .and_then(|counter| counter.parse::<u32>().ok())
+      .map(|counter| counter + 1)
+      .unwrap_or(0);
+
+    Ok(format!("{}-{}.{}", target, tag, next_pre))
   }
 }
 
 impl VersionEditor for Semantic {
-  // TODO: add mask validation
-  fn mask<'a>(mask: &str, version: &'a str) -> &'a str {
-    &version[..mask.len()]
+  fn release(version: &str) -> Result<String, VersionBumpError> {
+    Ok(version.parse::<SemanticValue>()?.release().to_string())
   }
 
+  fn compare(version: &str, other: &str) -> Result<Ordering, VersionBumpError> {
+    Ok(
+      version
+        .parse::<SemanticValue>()?
+        .precedence(&other.parse::<SemanticValue>()?),
+    )
+  }
+
+  /// Rewrites a cargo requirement (`0.3`, `^1.2`, `~0.3`, `>=1, <2`, `=1.2.3`, `1.*`, ...) so it
+  /// is satisfied by `version`, invalid requirements are replaced by the version itself
+  fn mask(mask: &str, version: &str) -> String {
+    if Self::r#match(mask, version) {
+      return mask.to_owned();
+    }
+
+    Self::rewrite(mask, version)
+  }
+
+  fn rewrite(mask: &str, version: &str) -> String {
+    let parsed = match semver::Version::parse(version) {
+      Ok(parsed) if VersionReq::parse(mask).is_ok() => parsed,
+      _ => return version.to_owned(),
+    };
+
+    mask
+      .split(',')
+      .map(|comparator| mask_comparator(comparator, &parsed))
+      .collect::<Vec<String>>()
+      .join(", ")
+  }
+
+  /// Whether the cargo requirement is satisfied by `version`, requirements on a pre-release are
+  /// never satisfied by a release so dependents don't keep depending on the pre-release
   fn r#match(mask: &str, version: &str) -> bool {
-    Self::mask(mask, version) == mask
+    match (VersionReq::parse(mask), semver::Version::parse(version)) {
+      (Ok(requirement), Ok(version)) => {
+        let pre_release_requirement = requirement
+          .comparators
+          .iter()
+          .any(|comparator| !comparator.pre.is_empty());
+
+        requirement.matches(&version) && (!pre_release_requirement || !version.pre.is_empty())
+      }
+      _ => mask == version,
+    }
+  }
+
+  fn with_build_metadata(next: &str, current: &str) -> Result<String, VersionBumpError> {
+    Ok(
+      next
+        .parse::<SemanticValue>()?
+        .with_build_metadata_of(&current.parse()?)
+        .to_string(),
+    )
   }
 
   fn options() -> Vec<Self> {
     vec![Self::patch(), Self::minor(), Self::major()]
   }
+
+  /// A 0.x minor is already a breaking change for cargo, so a major keeps the package under 1.0
+  fn initial_development(&self, current: &str) -> Self {
+    match current.parse::<SemanticValue>() {
+      Ok(current) if current.major() == 0 && self.r#type == SemanticVersion::Major => Self::minor(),
+      _ => self.clone(),
+    }
+  }
+
+  /// A pre-release next version gets the snapshot as another identifier (1.2.0-beta.1.snapshot)
+  fn snapshot(next: &str, snapshot: &str) -> Result<String, VersionBumpError> {
+    let next = next.parse::<SemanticValue>()?;
+
+    Ok(match next.pre_release() {
+      Some(_) => format!("{}.{}", next, snapshot),
+      None => format!("{}-{}", next, snapshot),
+    })
+  }
 }
 
 impl Default for Semantic {
@@ -98,14 +333,13 @@ impl FromStr for Semantic {
   }
 }
 
-impl ToString for Semantic {
-  fn to_string(&self) -> String {
-    match self.r#type {
+impl fmt::Display for Semantic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self.r#type {
       SemanticVersion::Patch => "patch",
       SemanticVersion::Minor => "minor",
       SemanticVersion::Major => "major",
-    }
-    .to_owned()
+    })
   }
 }
 
@@ -117,7 +351,7 @@ mod tests {
 
   #[test]
   fn from_str() {
-    let strings = vec!["patch", "minor", "minor", "major"];
+    let strings = ["patch", "minor", "minor", "major"];
 
     let versions: Vec<VersionMod<Semantic>> = strings
       .iter()
@@ -137,7 +371,7 @@ mod tests {
 
   #[test]
   fn to_str() {
-    let versions = vec![
+    let versions = [
       VersionMod::new(Semantic::patch()),
       VersionMod::new(Semantic::minor()),
       VersionMod::new(Semantic::minor()),
@@ -149,17 +383,235 @@ mod tests {
     assert_eq!(strings, vec!["patch", "minor", "minor", "major"]);
   }
 
-  // TODO: this
-  // #[test]
-  // fn canary_apply() {
-  //   let version = VersionMod::new(Semantic::major());
+  #[test]
+  fn pre_release_apply() {
+    let version = VersionMod::new(Semantic::minor());
 
-  //   let bumped = version.apply("0.0.1-alpha.0");
+    assert_eq!(version.apply("1.2.0-beta.1").unwrap(), "1.2.0".to_owned());
+    assert_eq!(version.apply("1.2.3-beta.1").unwrap(), "1.3.0".to_owned());
 
-  //   assert!(bumped.is_ok());
+    let version = VersionMod::new(Semantic::patch());
 
-  //   assert_eq!(bumped.unwrap(), "0.0.2".to_owned())
-  // }
+    assert_eq!(version.apply("1.2.3-beta.1").unwrap(), "1.2.3".to_owned());
+  }
+
+  #[test]
+  fn apply_pre_release() {
+    let version = VersionMod::new(Semantic::minor());
+
+    assert_eq!(
+      version.apply_pre_release("1.1.4", "1.1.4", "beta").unwrap(),
+      "1.2.0-beta.0".to_owned()
+    );
+    assert_eq!(
+      version
+        .apply_pre_release("1.1.4", "1.2.0-beta.0", "beta")
+        .unwrap(),
+      "1.2.0-beta.1".to_owned()
+    );
+    assert_eq!(
+      version
+        .apply_pre_release("1.1.4", "1.2.0-alpha.3", "beta")
+        .unwrap(),
+      "1.2.0-beta.0".to_owned()
+    );
+
+    let version = VersionMod::new(Semantic::major());
+
+    assert_eq!(
+      version
+        .apply_pre_release("1.1.4", "1.2.0-beta.1", "beta")
+        .unwrap(),
+      "2.0.0-beta.0".to_owned()
+    );
+  }
+
+  #[test]
+  fn snapshot() {
+    assert_eq!(
+      Semantic::snapshot("0.3.1", "snapshot-20261018123045").unwrap(),
+      "0.3.1-snapshot-20261018123045"
+    );
+    assert_eq!(
+      Semantic::snapshot("0.4.0-beta.1", "snapshot-20261018123045").unwrap(),
+      "0.4.0-beta.1.snapshot-20261018123045"
+    );
+  }
+
+  #[test]
+  fn release() {
+    assert_eq!(Semantic::release("1.2.0-beta.1").unwrap(), "1.2.0");
+    assert_eq!(Semantic::release("1.2.0+build5").unwrap(), "1.2.0");
+    assert_eq!(Semantic::release("1.2.0").unwrap(), "1.2.0");
+  }
+
+  #[test]
+  fn compare() {
+    assert_eq!(
+      Semantic::compare("1.2.0", "1.10.0").unwrap(),
+      Ordering::Less
+    );
+    assert_eq!(
+      Semantic::compare("1.2.0", "1.2.0").unwrap(),
+      Ordering::Equal
+    );
+    assert_eq!(
+      Semantic::compare("1.2.0", "1.2.0-beta.0").unwrap(),
+      Ordering::Greater
+    );
+    assert!(Semantic::compare("1.2", "1.2.0").is_err());
+  }
+
+  #[test]
+  fn semantic_value() {
+    let value = "1.0.0-rc.1+build5".parse::<SemanticValue>().unwrap();
+
+    assert_eq!(value.major(), 1);
+    assert_eq!(value.pre_release(), Some("rc.1"));
+    assert_eq!(value.build_metadata(), Some("build5"));
+    assert_eq!(value.release(), SemanticValue::new(1, 0, 0));
+    assert_eq!(value.to_string(), "1.0.0-rc.1+build5");
+
+    assert_eq!(
+      Version::<Semantic>::from("1.2.3").semantic().unwrap(),
+      SemanticValue::new(1, 2, 3)
+    );
+  }
+
+  #[test]
+  fn semantic_value_error() {
+    assert_eq!(
+      "1.x.0".parse::<SemanticValue>().unwrap_err().to_string(),
+      "Invalid version \"1.x.0\", unexpected character 'x' while parsing minor version number"
+    );
+    assert_eq!(
+      "1.2".parse::<SemanticValue>().unwrap_err().to_string(),
+      "Invalid version \"1.2\", unexpected end of input while parsing minor version number"
+    );
+    assert!(VersionMod::new(Semantic::patch()).apply("1.02.0").is_err());
+  }
+
+  #[test]
+  fn precedence() {
+    let versions = [
+      "1.0.0-alpha",
+      "1.0.0-alpha.1",
+      "1.0.0-alpha.beta",
+      "1.0.0-beta",
+      "1.0.0-beta.2",
+      "1.0.0-beta.11",
+      "1.0.0-rc.1",
+      "1.0.0",
+    ];
+
+    for pair in versions.windows(2) {
+      assert_eq!(
+        Semantic::compare(pair[0], pair[1]).unwrap(),
+        Ordering::Less,
+        "{} < {}",
+        pair[0],
+        pair[1]
+      );
+    }
+
+    assert_eq!(
+      Semantic::compare("1.0.0+build5", "1.0.0+build6").unwrap(),
+      Ordering::Equal
+    );
+
+    let value = |version: &str| version.parse::<SemanticValue>().unwrap();
+
+    assert!(value("1.0.0-rc.1") < value("1.0.0"));
+    assert!(value("1.0.0+build6") < value("1.0.1+build5"));
+    assert!(value("1.0.0+build5") < value("1.0.0+build6"));
+    assert_eq!(
+      value("1.0.0+build5").precedence(&value("1.0.0+build6")),
+      Ordering::Equal
+    );
+  }
+
+  #[test]
+  fn build_metadata() {
+    let version = VersionMod::new(Semantic::minor());
+
+    assert_eq!(version.apply("1.0.0+build5").unwrap(), "1.1.0");
+    assert_eq!(version.apply("1.0.0-rc.1+build5").unwrap(), "1.0.0");
+    assert_eq!(
+      Semantic::with_build_metadata("1.1.0", "1.0.0+build5").unwrap(),
+      "1.1.0+build5"
+    );
+    assert_eq!(
+      Semantic::with_build_metadata("1.1.0", "1.0.0").unwrap(),
+      "1.1.0"
+    );
+  }
+
+  #[test]
+  fn pre_release_mask() {
+    assert_eq!(Semantic::mask("0.1", "0.2.0-beta.0"), "0.2.0-beta.0");
+    assert!(!Semantic::r#match("0.2", "0.2.0-beta.0"));
+    assert!(Semantic::r#match("0.2.0-beta.0", "0.2.0-beta.0"));
+    assert!(!Semantic::r#match("0.2.0-beta.0", "0.2.0"));
+  }
+
+  #[test]
+  fn mask() {
+    assert_eq!(Semantic::mask("0.9", "0.10.0"), "0.10");
+    assert_eq!(Semantic::mask("^1.2", "2.0.0"), "^2.0");
+    assert_eq!(Semantic::mask("~0.3", "0.4.1"), "~0.4");
+    assert_eq!(Semantic::mask("=1.2.3", "1.3.0"), "=1.3.0");
+    assert_eq!(Semantic::mask(">=1, <2", "2.1.0"), ">=2, <3");
+    assert_eq!(Semantic::mask(">= 1.2, < 1.5", "1.5.3"), ">= 1.5, < 1.6");
+    assert_eq!(Semantic::mask("1.*", "2.0.0"), "2.*");
+    assert_eq!(Semantic::mask("*", "2.0.0"), "*");
+    assert_eq!(Semantic::mask("0.3", "0.3.4"), "0.3");
+    assert_eq!(Semantic::mask("not a requirement", "0.3.4"), "0.3.4");
+  }
+
+  #[test]
+  fn rewrite() {
+    assert_eq!(Semantic::rewrite("1.2", "1.3.0"), "1.3");
+    assert_eq!(Semantic::rewrite("~1.2.1", "1.2.4"), "~1.2.4");
+    assert_eq!(Semantic::rewrite("*", "2.0.0"), "*");
+  }
+
+  #[test]
+  fn mask_initial_development() {
+    assert_eq!(Semantic::mask("0.3", "0.4.0"), "0.4");
+    assert_eq!(Semantic::mask("^0.3.1", "0.4.0"), "^0.4.0");
+    assert_eq!(Semantic::mask("0.0.3", "0.0.4"), "0.0.4");
+    assert!(Semantic::r#match("0.3", "0.3.9"));
+    assert!(!Semantic::r#match("0.3", "0.4.0"));
+    assert!(!Semantic::r#match("0.0.3", "0.0.4"));
+  }
+
+  #[test]
+  fn initial_development() {
+    assert_eq!(
+      Semantic::major().initial_development("0.3.1"),
+      Semantic::minor()
+    );
+    assert_eq!(
+      Semantic::patch().initial_development("0.3.1"),
+      Semantic::patch()
+    );
+    assert_eq!(
+      Semantic::major().initial_development("1.3.1"),
+      Semantic::major()
+    );
+  }
+
+  #[test]
+  fn r#match() {
+    assert!(Semantic::r#match("0.9", "0.9.3"));
+    assert!(!Semantic::r#match("0.9", "0.10.0"));
+    assert!(Semantic::r#match("^1.2", "1.10.0"));
+    assert!(!Semantic::r#match("~0.3", "0.4.0"));
+    assert!(Semantic::r#match(">=1, <2", "1.9.9"));
+    assert!(!Semantic::r#match(">=1, <2", "2.0.0"));
+    assert!(!Semantic::r#match("=1.2.3", "1.2.4"));
+    assert!(Semantic::r#match("*", "5.0.0"));
+  }
 
   #[test]
   fn major_apply() {
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::fmt;
 use std::hash::Hash;
 use std::marker::PhantomData;
 use std::str::FromStr;
@@ -10,13 +12,39 @@ pub trait VersionEditor:
 {
   fn options() -> Vec<Self>;
 
-  fn mask<'a>(mask: &str, version: &'a str) -> &'a str;
+  fn release(version: &str) -> Result<String, VersionBumpError>;
+
+  fn compare(version: &str, other: &str) -> Result<Ordering, VersionBumpError>;
+
+  fn mask(mask: &str, version: &str) -> String;
+
+  /// Requirement `mask` rewritten for `version` even when it already matches, editors that keep
+  /// no precision fall back to [`VersionEditor::mask`]
+  fn rewrite(mask: &str, version: &str) -> String {
+    Self::mask(mask, version)
+  }
 
   fn r#match(mask: &str, version: &str) -> bool;
+
+  /// `next` carrying over the build metadata of `current`
+  fn with_build_metadata(next: &str, current: &str) -> Result<String, VersionBumpError>;
+
+  /// Bump applied instead of this one while the package at `current` is in initial development
+  fn initial_development(&self, current: &str) -> Self;
+
+  /// Snapshot of the `next` version, ie 0.3.1-snapshot-20261018123045
+  fn snapshot(next: &str, snapshot: &str) -> Result<String, VersionBumpError>;
 }
 
 pub trait Versioned: ToString {
   fn apply(&self, current: &str) -> Result<String, VersionBumpError>;
+
+  fn apply_pre_release(
+    &self,
+    initial: &str,
+    current: &str,
+    tag: &str,
+  ) -> Result<String, VersionBumpError>;
 }
 
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Ord, PartialOrd)]
@@ -37,18 +65,47 @@ where
   fn apply(&self, current: &str) -> Result<String, VersionBumpError> {
     self.version.apply(current)
   }
+
+  fn apply_pre_release(
+    &self,
+    initial: &str,
+    current: &str,
+    tag: &str,
+  ) -> Result<String, VersionBumpError> {
+    self.version.apply_pre_release(initial, current, tag)
+  }
 }
 
 impl<T> VersionEditor for VersionMod<T>
 where
   T: VersionEditor,
 {
-  fn mask<'a>(mask: &str, version: &'a str) -> &'a str {
+  fn release(version: &str) -> Result<String, VersionBumpError> {
+    T::release(version)
+  }
+  fn compare(version: &str, other: &str) -> Result<Ordering, VersionBumpError> {
+    T::compare(version, other)
+  }
+  fn mask(mask: &str, version: &str) -> String {
     T::mask(mask, version)
   }
+  fn rewrite(mask: &str, version: &str) -> String {
+    T::rewrite(mask, version)
+  }
   fn r#match(mask: &str, version: &str) -> bool {
     T::r#match(mask, version)
   }
+  fn with_build_metadata(next: &str, current: &str) -> Result<String, VersionBumpError> {
+    T::with_build_metadata(next, current)
+  }
+  fn snapshot(next: &str, snapshot: &str) -> Result<String, VersionBumpError> {
+    T::snapshot(next, snapshot)
+  }
+  fn initial_development(&self, current: &str) -> Self {
+    Self {
+      version: self.version.initial_development(current),
+    }
+  }
   fn options() -> Vec<Self> {
     T::options()
       .into_iter()
@@ -69,12 +126,12 @@ where
   }
 }
 
-impl<T> ToString for VersionMod<T>
+impl<T> fmt::Display for VersionMod<T>
 where
   T: ToString,
 {
-  fn to_string(&self) -> String {
-    self.version.to_string()
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.version.to_string())
   }
 }
 
@@ -17,6 +17,10 @@ pub enum Command {
   Version(Version),
   /// Publish the new versions of all the pacakges that were updated by mol version
   Publish(Publish),
+  /// Enter or exit the pre-release mode
+  Pre(Pre),
+  /// Show the pending releases without changing anything
+  Status(Status),
 }
 
 impl<T, V> IntoExecutableCommand<T, V> for Command
@@ -32,6 +36,8 @@ where
       Self::Init(init) => Some(init as &dyn ExecutableCommand<T, V>),
       Self::Version(version) => Some(version as &dyn ExecutableCommand<T, V>),
       Self::Publish(publish) => Some(publish as &dyn ExecutableCommand<T, V>),
+      Self::Pre(pre) => Some(pre as &dyn ExecutableCommand<T, V>),
+      Self::Status(status) => Some(status as &dyn ExecutableCommand<T, V>),
     }
   }
 }
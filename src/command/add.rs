@@ -55,6 +55,18 @@ impl Add {
     }
 
     if !self.packages.is_empty() {
+      if let Some(name) = self.packages.iter().find(|name| {
+        !context
+          .packages
+          .iter()
+          .any(|package| package.name == **name)
+      }) {
+        return Err(anyhow::anyhow!(
+          "Package \"{}\" was not found or is ignored in config",
+          name
+        ));
+      }
+
       let packages = context
         .packages
         .iter()
@@ -115,6 +127,7 @@ impl Add {
     };
 
     let changeset: Changeset<V> = Changeset {
+      id: Changeset::<V>::random_id(),
       packages: packages
         .into_iter()
         .map(|package| (package.name, version.clone()))
@@ -144,13 +157,13 @@ where
       let changeset_path = {
         let mut path = context.changesets.directory.clone();
 
-        path.push(Changeset::<V>::random_file_name());
+        path.push(changeset.file_name());
 
         path
       };
 
       if context.dry_run {
-        println!("{}", changeset.to_string());
+        println!("{}", changeset);
       } else {
         changeset
           .save(&changeset_path)
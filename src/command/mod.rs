@@ -3,19 +3,30 @@ use std::sync::Arc;
 
 use anyhow::Context;
 use async_trait::async_trait;
+use clap::ArgEnum;
 
 use mol_core::prelude::*;
 
 mod add;
 mod init;
+mod pre;
 mod publish;
+mod status;
 mod version;
 
 pub use add::Add;
 pub use init::Init;
+pub use pre::Pre;
 pub use publish::Publish;
+pub use status::Status;
 pub use version::Version;
 
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+  Text,
+  Json,
+}
+
 pub struct ExecutableContext<T: PackageManager, V: VersionEditor> {
   pub changesets: Changesets,
   pub dry_run: bool,
@@ -30,6 +41,14 @@ where
   T: PackageManager,
   V: VersionEditor,
 {
+  /// Graph of the workspace packages configured by the mol config
+  pub fn package_graph(&self) -> PackageGraph<'_, V> {
+    self
+      .packages
+      .as_package_graph()
+      .with_ignore_dev_cycles(self.changesets.config.ignore_dev_cycles)
+  }
+
   pub fn as_plugin(&self) -> PluginContext<'_> {
     PluginContext {
       dry_run: self.dry_run,
@@ -58,12 +77,28 @@ where
       .await
       .with_context(|| format!("Validation error for package at dir {:?}", package_path))?;
 
+    let mut changesets = Changesets::load()
+      .await
+      .context("Could not load the changesets config")?;
+
+    let ignore = changesets
+      .config
+      .ignore_globs()
+      .context("Invalid ignore pattern in config")?;
+
     let packages = T::seek_packages(&package_path, &metadata)
       .await
-      .with_context(|| format!("Could not open read pacakges at dir {:?}", package_path))?;
+      .with_context(|| format!("Could not open read pacakges at dir {:?}", package_path))?
+      .into_iter()
+      .filter(|package| !ignore.is_match(&package.name))
+      .collect::<Vec<Package<V>>>();
+
+    changesets.config.shared_version = T::shared_version_groups(&packages, &metadata)
+      .await
+      .context("Could not read the versions shared by packages")?;
 
     Ok(ExecutableContext {
-      changesets: Changesets::default(),
+      changesets,
       dry_run,
       package_manager,
       packages,
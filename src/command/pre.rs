@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::Parser;
+use tokio::fs;
+
+use mol_core::prelude::*;
+
+use super::{ExecutableCommand, ExecutableContext, Version};
+
+#[derive(Parser, Debug)]
+pub enum PreCommand {
+  /// Enter pre-release mode, `mol version` will produce versions like 1.2.0-<tag>.0
+  Enter { tag: String },
+  /// Exit pre-release mode and release the stable versions of all the pre-released packages
+  Exit,
+}
+
+#[derive(Parser, Debug)]
+pub struct Pre {
+  #[clap(subcommand)]
+  pub cmd: PreCommand,
+}
+
+impl Pre {
+  async fn enter<T, V>(&self, context: &ExecutableContext<T, V>, tag: &str) -> anyhow::Result<()>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    if let Some(pre_release) = context.changesets.load_pre_release().await? {
+      return Err(anyhow::anyhow!(
+        "Already in pre-release mode with tag \"{}\"",
+        pre_release.tag
+      ));
+    }
+
+    let mut pre_release = PreRelease::new(
+      tag.to_owned(),
+      context
+        .packages
+        .iter()
+        .map(|package| (package.name.clone(), package.version.value.clone()))
+        .collect(),
+    );
+
+    for package in &context.packages {
+      let requirements: BTreeMap<String, String> = package
+        .dependencies
+        .iter()
+        .filter(|dependency| pre_release.initial_versions.contains_key(&dependency.name))
+        .map(|dependency| (dependency.name.clone(), dependency.version.clone()))
+        .collect();
+
+      if !requirements.is_empty() {
+        pre_release
+          .initial_requirements
+          .insert(package.name.clone(), requirements);
+      }
+    }
+
+    if context.dry_run {
+      println!("dry_run - enter pre-release mode: {}", tag);
+    } else {
+      pre_release
+        .save(context.changesets.pre_release_path())
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  async fn exit<T, V>(&self, context: &ExecutableContext<T, V>) -> anyhow::Result<()>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    let pre_release = context
+      .changesets
+      .load_pre_release()
+      .await?
+      .ok_or_else(|| anyhow::Error::msg("Not in pre-release mode, run 'pre enter <tag>'"))?;
+
+    let package_graph = context.package_graph();
+
+    let mut updated = HashMap::new();
+
+    for exit in pre_release.exit(&package_graph)? {
+      let package = exit.package;
+
+      if let Some(next_version) = &exit.next_version {
+        updated.insert(package.name.as_str(), next_version.clone());
+
+        if context.dry_run {
+          println!(
+            "dry_run - version bump: {} -> {}",
+            package.version.value, next_version
+          );
+        } else {
+          context
+            .package_manager
+            .apply_version(&package.path, next_version, &context.metadata)
+            .await?;
+        }
+      }
+
+      for update in &exit.dependencies {
+        if context.dry_run {
+          println!(
+            "dry_run - dependecy version bump: {} {} -> {}",
+            update.dependency.name, update.dependency.version, update.next_version
+          );
+        } else {
+          context
+            .package_manager
+            .apply_dependency_version(
+              &package.path,
+              update.dependency,
+              &update.next_version,
+              &context.metadata,
+            )
+            .await?;
+        }
+      }
+
+      if let (Some(next_version), Some(root_path)) = (exit.next_version, package.path.parent()) {
+        let changelog_path = {
+          let mut root_path = root_path.to_path_buf();
+          root_path.push(&context.changesets.config.changelog);
+          root_path
+        };
+
+        Changelog::fold_pre_release::<_, V>(&changelog_path, next_version.into(), context.dry_run)
+          .await
+          .with_context(|| {
+            format!(
+              "Could not update the changelog for {} at {:?}",
+              package.name, changelog_path
+            )
+          })?;
+      }
+    }
+
+    Version::update_lockfile(context, &updated).await?;
+
+    let changeset_paths = pre_release
+      .changesets
+      .iter()
+      .map(|name| context.changesets.directory.join(name))
+      .filter(|changeset_path| changeset_path.exists())
+      .chain(Some(context.changesets.pre_release_path()));
+
+    for changeset_path in changeset_paths {
+      if context.dry_run {
+        println!("dry_run - delete: {:?}", changeset_path);
+      } else {
+        fs::remove_file(&changeset_path)
+          .await
+          .with_context(|| format!("Unable to remove {:?}", changeset_path))?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl<T, V> ExecutableCommand<T, V> for Pre
+where
+  T: PackageManager + Send + Sync,
+  V: VersionEditor + Send + Sync + 'static,
+  T::Metadata: Send + Sync,
+{
+  async fn execute(
+    &self,
+    context: &ExecutableContext<T, V>,
+    plugins: Arc<PluginManager>,
+  ) -> anyhow::Result<()> {
+    plugins.pre_command("pre", &context.as_plugin())?;
+
+    match &self.cmd {
+      PreCommand::Enter { tag } => self.enter(context, tag).await?,
+      PreCommand::Exit => self.exit(context).await?,
+    }
+
+    plugins.post_command("pre", &context.as_plugin())?;
+
+    Ok(())
+  }
+}
@@ -1,9 +1,12 @@
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use anyhow::Context;
 use async_trait::async_trait;
 use clap::Parser;
+use futures::{stream, Future, StreamExt};
 
 use mol_core::prelude::*;
 
@@ -15,6 +18,181 @@ pub struct Publish {
   pub packages: Vec<String>,
   #[clap(long)]
   pub publish_args: Vec<String>,
+  /// Registry to publish to and check published versions against, overrides the config
+  #[clap(long)]
+  pub registry: Option<String>,
+  /// Seconds to wait per package for it to show up in the registry, overrides the config
+  #[clap(long)]
+  pub timeout: Option<u64>,
+  /// Maximum number of registry checks for every published package, overrides the config
+  #[clap(long)]
+  pub max_attempts: Option<u32>,
+  /// Number of packages without dependencies on each other that are published concurrently
+  #[clap(short, long, default_value = "1")]
+  pub jobs: usize,
+  /// Publish the versions of `mol version --snapshot`, only packages with pending changesets and
+  /// their transitive dependents are published
+  #[clap(long)]
+  pub snapshot: bool,
+}
+
+impl Publish {
+  fn publish_wait(&self, config: &Config) -> PublishWait {
+    let mut wait = config.publish_wait.clone();
+
+    if let Some(timeout) = self.timeout {
+      wait.timeout = timeout;
+    }
+
+    if let Some(max_attempts) = self.max_attempts {
+      wait.max_attempts = max_attempts;
+    }
+
+    wait
+  }
+
+  /// Whether the package is selected, in snapshot mode only packages that got a snapshot version
+  /// (packages with pending changesets and their transitive dependents)
+  fn includes<V: VersionEditor>(&self, package: &Package<V>, snapshotted: &HashSet<&str>) -> bool {
+    (self.packages.is_empty() || self.packages.contains(&package.name))
+      && (!self.snapshot || snapshotted.contains(package.name.as_str()))
+  }
+
+  /// Runs up to `jobs` futures at a time, the first error is returned only once every future is
+  /// done so no publish is left half way
+  async fn join_all<R, F>(futures: Vec<F>, jobs: usize) -> anyhow::Result<Vec<R>>
+  where
+    F: Future<Output = anyhow::Result<R>>,
+  {
+    stream::iter(futures)
+      .buffer_unordered(jobs)
+      .collect::<Vec<anyhow::Result<R>>>()
+      .await
+      .into_iter()
+      .collect()
+  }
+
+  /// Publishes the package unless it is private or already published, returns the package when it
+  /// was published
+  async fn publish_package<'a, T, V>(
+    &self,
+    context: &ExecutableContext<T, V>,
+    package: &'a Package<V>,
+    registry: &Registry,
+  ) -> anyhow::Result<Option<&'a Package<V>>>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    if !package.publish {
+      println!("Skipping private package {}", package.name);
+      return Ok(None);
+    }
+
+    if context
+      .package_manager
+      .check_version(package, registry, &context.metadata)
+      .await
+      .with_context(|| {
+        format!(
+          "Unable to check if {}@{} is already published",
+          package.name, package.version.value
+        )
+      })?
+      == VersionCheck::Published
+    {
+      println!(
+        "Skipping {}@{}, already published",
+        package.name, package.version.value
+      );
+      return Ok(None);
+    }
+
+    let root_path = match package.path.parent() {
+      Some(root_path) => root_path,
+      None => return Ok(None),
+    };
+
+    context
+      .package_manager
+      .run_publish(
+        root_path,
+        context
+          .changesets
+          .config
+          .publish_args
+          .iter()
+          .chain(&self.publish_args)
+          .cloned()
+          .collect(),
+        registry,
+        context.dry_run,
+        self.jobs > 1,
+        &context.metadata,
+      )
+      .await
+      .with_context(|| format!("Failed publishing {}", package.name))?;
+
+    Ok(Some(package))
+  }
+
+  /// Polls the registry until the published version shows up, with exponential backoff until
+  /// either the timeout or the maximum attempts are reached
+  async fn wait_for_publish<T, V>(
+    context: &ExecutableContext<T, V>,
+    package: &Package<V>,
+    registry: &Registry,
+    wait: &PublishWait,
+  ) -> anyhow::Result<()>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    let started = Instant::now();
+    let timeout = Duration::from_secs(wait.timeout);
+
+    let mut attempt = 0;
+
+    loop {
+      attempt += 1;
+
+      let last_response = match context
+        .package_manager
+        .check_version(package, registry, &context.metadata)
+        .await
+      {
+        Ok(VersionCheck::Published) => return Ok(()),
+        Ok(VersionCheck::Missing(response)) => response,
+        Err(err) => format!("{:#}", err),
+      };
+
+      let remaining = timeout.saturating_sub(started.elapsed());
+
+      if attempt >= wait.max_attempts || remaining.is_zero() {
+        return Err(anyhow::anyhow!(
+          "Gave up waiting for {}@{} to show up in the registry after {} attempts in {}s, last registry response: {}",
+          package.name,
+          package.version.value,
+          attempt,
+          started.elapsed().as_secs(),
+          last_response
+        ));
+      }
+
+      let delay = wait.delay(attempt).min(remaining);
+
+      println!(
+        "Package {} didn't update yet ({}), checking again in {}ms",
+        package.name,
+        last_response,
+        delay.as_millis()
+      );
+
+      tokio::time::sleep(delay).await;
+    }
+  }
 }
 
 #[async_trait]
@@ -31,47 +209,83 @@ where
   ) -> anyhow::Result<()> {
     plugins.pre_command("publish", &context.as_plugin())?;
 
-    let graph = context.packages.as_package_graph();
+    let graph = context.package_graph();
+
+    let registry = context
+      .changesets
+      .config
+      .registry
+      .select(self.registry.as_deref());
+    let wait = self.publish_wait(&context.changesets.config);
+
+    let pre_release = context.changesets.load_pre_release().await?;
 
-    let (changeset_files, _) = context.changesets.consume::<V>(&graph).await?;
+    let (changeset_files, bump) = context
+      .changesets
+      .consume_pending::<V>(&graph, pre_release.as_ref())
+      .await?;
 
-    if !changeset_files.is_empty() {
+    let snapshotted = graph.transitive_dependents(
+      graph
+        .update_order()?
+        .into_iter()
+        .filter(|package| bump.package(&package.name).version().is_some())
+        .map(|package| package.name.as_str())
+        .collect(),
+    );
+
+    if !changeset_files.is_empty() && !self.snapshot {
       println!("Changesents found, skipping publish");
     } else {
-      let packages = if self.packages.is_empty() {
-        graph.update_order()
-      } else {
-        graph
-          .update_order()
+      let jobs = self.jobs.max(1);
+
+      context
+        .package_manager
+        .prepare_publish(&context.root_dir, &context.metadata)
+        .await?;
+
+      // published in this run but not yet confirmed to be visible in the registry
+      let mut unconfirmed: Vec<&Package<V>> = Vec::new();
+
+      for level in graph.levels()? {
+        let level: Vec<&Package<V>> = level
           .into_iter()
-          .filter(|package| self.packages.contains(&package.name))
-          .collect()
-      };
+          .filter(|package| self.includes(package, &snapshotted))
+          .collect();
+
+        let (required, rest): (Vec<&Package<V>>, Vec<&Package<V>>) =
+          unconfirmed.into_iter().partition(|published| {
+            level.iter().any(|package| {
+              graph
+                .internal_dependencies(package)
+                .contains(&published.name.as_str())
+            })
+          });
+        unconfirmed = rest;
 
-      for package in &packages {
-        if let Some(root_path) = package.path.parent() {
-          context
-            .package_manager
-            .run_publish(
-              root_path,
-              self.publish_args.clone(),
-              context.dry_run,
-              &context.metadata,
-            )
-            .await?;
-
-          if !context.dry_run {
-            while !context
-              .package_manager
-              .check_version(package, &context.metadata)
-              .await?
-            {
-              println!("Package didn't upate yet");
-
-              tokio::time::sleep(Duration::from_secs(1)).await;
-            }
+        if !context.dry_run {
+          let mut waits = Vec::new();
+          for package in required {
+            waits.push(Self::wait_for_publish(context, package, &registry, &wait));
           }
+          Self::join_all(waits, jobs).await?;
+        }
+
+        let mut publishes = Vec::new();
+        for package in level {
+          publishes.push(self.publish_package(context, package, &registry));
         }
+        let published = Self::join_all(publishes, jobs).await?;
+
+        unconfirmed.extend(published.into_iter().flatten());
+      }
+
+      if !context.dry_run {
+        let mut waits = Vec::new();
+        for package in unconfirmed {
+          waits.push(Self::wait_for_publish(context, package, &registry, &wait));
+        }
+        Self::join_all(waits, jobs).await?;
       }
     }
 
@@ -80,3 +294,57 @@ where
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn includes() {
+    let packages: Vec<Package<Semantic>> = vec![
+      Package {
+        name: "mol-core".to_owned(),
+        path: "".into(),
+        version: "0.3.1-snapshot-20261018123045".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+      Package {
+        name: "mol".to_owned(),
+        path: "".into(),
+        version: "0.3.1-snapshot-20261018123045".into(),
+        dependencies: vec![Dependency::new(
+          "mol-core",
+          "=0.3.1-snapshot-20261018123045",
+        )],
+        publish: true,
+      },
+      Package {
+        name: "mol-docs".to_owned(),
+        path: "".into(),
+        version: "0.3.0".into(),
+        dependencies: vec![],
+        publish: true,
+      },
+    ];
+    let graph = packages.as_package_graph();
+
+    let snapshotted = graph.transitive_dependents(vec!["mol-core"]);
+
+    let publish = Publish::parse_from(["publish"]);
+
+    assert!(publish.includes(&packages[2], &snapshotted));
+
+    let publish = Publish::parse_from(["publish", "--snapshot"]);
+
+    assert!(publish.includes(&packages[0], &snapshotted));
+    assert!(publish.includes(&packages[1], &snapshotted));
+    assert!(!publish.includes(&packages[2], &snapshotted));
+
+    let publish = Publish::parse_from(["publish", "--snapshot", "--packages", "mol"]);
+
+    assert!(!publish.includes(&packages[0], &snapshotted));
+    assert!(publish.includes(&packages[1], &snapshotted));
+  }
+}
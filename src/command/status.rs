@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use clap::Parser;
+
+use mol_core::prelude::*;
+
+use super::{ExecutableCommand, ExecutableContext, OutputFormat};
+
+#[derive(Parser, Debug)]
+pub struct Status {
+  /// Output format, json prints the release plan as described in the README
+  #[clap(long, arg_enum, default_value = "text")]
+  pub output: OutputFormat,
+}
+
+impl Status {
+  fn print_release<V: VersionEditor>(changesets: &Changesets, release: &PackageRelease<'_, V>) {
+    println!(
+      "{}: {} -> {} ({})",
+      release.package.name, release.package.version.value, release.next_version, release.version
+    );
+
+    if !release.package.publish {
+      println!("  private, not published");
+    }
+
+    match release.reason {
+      ReleaseReason::Changeset => println!(
+        "  changesets: {}",
+        release
+          .changesets
+          .iter()
+          .map(|changeset| changesets
+            .directory
+            .join(changeset.file_name())
+            .display()
+            .to_string())
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      ReleaseReason::Dependent => println!("  bumped as a dependent"),
+      ReleaseReason::FixedGroup => println!("  released with its fixed group"),
+    }
+
+    if !release.linked.is_empty() {
+      println!("  linked with: {}", release.linked.join(", "));
+    }
+
+    if !release.dependents.is_empty() {
+      println!("  dependents: {}", release.dependents.join(", "));
+    }
+  }
+}
+
+#[async_trait]
+impl<T, V> ExecutableCommand<T, V> for Status
+where
+  T: PackageManager + Send + Sync,
+  V: VersionEditor + Send + Sync + 'static,
+  T::Metadata: Send + Sync,
+{
+  async fn execute(
+    &self,
+    context: &ExecutableContext<T, V>,
+    plugins: Arc<PluginManager>,
+  ) -> anyhow::Result<()> {
+    plugins.pre_command("status", &context.as_plugin())?;
+
+    let pre_release = context.changesets.load_pre_release().await?;
+
+    let package_graph = context.package_graph();
+    let (_, bump) = context
+      .changesets
+      .consume_pending::<V>(&package_graph, pre_release.as_ref())
+      .await?;
+
+    let plan = match &pre_release {
+      Some(pre_release) => {
+        let (_, pre_release_bump) = context
+          .changesets
+          .consume::<V>(&package_graph, Some(pre_release))
+          .await?;
+
+        ReleasePlan::pre_release(&package_graph, &bump, pre_release, &pre_release_bump)?
+      }
+      None => ReleasePlan::new(&package_graph, &bump)?,
+    }
+    .with_keep_build_metadata(context.changesets.config.keep_build_metadata)?;
+
+    match self.output {
+      OutputFormat::Json => println!(
+        "{}",
+        serde_json::to_string_pretty(
+          &plan.report(&context.changesets.config.changelog, pre_release.as_ref())
+        )?
+      ),
+      OutputFormat::Text => {
+        if let Some(pre_release) = &pre_release {
+          println!("In pre-release mode with tag \"{}\"\n", pre_release.tag);
+        }
+
+        if plan.packages.is_empty() {
+          println!(
+            "No pending changesets found in {:?}",
+            context.changesets.directory
+          );
+        }
+
+        for release in &plan.packages {
+          Self::print_release(&context.changesets, release);
+        }
+      }
+    }
+
+    plugins.post_command("status", &context.as_plugin())?;
+
+    Ok(())
+  }
+}
@@ -4,12 +4,13 @@ use std::sync::Arc;
 
 use anyhow::Context;
 use async_trait::async_trait;
+use chrono::Utc;
 use clap::Parser;
 use tokio::fs;
 
 use mol_core::prelude::*;
 
-use super::{ExecutableCommand, ExecutableContext};
+use super::{ExecutableCommand, ExecutableContext, OutputFormat};
 
 #[derive(Parser, Debug)]
 pub struct Version {
@@ -17,6 +18,140 @@ pub struct Version {
   pub no_build: bool,
   #[clap(long)]
   pub build_args: Vec<String>,
+  /// Version packages with pending changesets and their transitive dependents as
+  /// <next version>-<snapshot>-<timestamp> without consuming the changesets
+  #[clap(long)]
+  pub snapshot: Option<String>,
+  /// Output format, json prints the release plan as described in the README
+  #[clap(long, arg_enum, default_value = "text")]
+  pub output: OutputFormat,
+}
+
+impl Version {
+  async fn run_build<T, V>(&self, context: &ExecutableContext<T, V>) -> anyhow::Result<()>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    if !context.dry_run && !self.no_build {
+      context
+        .package_manager
+        .run_build(
+          &context.root_dir,
+          context
+            .changesets
+            .config
+            .build_args
+            .iter()
+            .chain(&self.build_args)
+            .cloned()
+            .collect(),
+          &context.metadata,
+        )
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  /// Applies the next version of the release and rewrites its requirements on released packages
+  async fn apply_release<T, V>(
+    context: &ExecutableContext<T, V>,
+    release: &PackageRelease<'_, V>,
+  ) -> anyhow::Result<()>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    let package = release.package;
+
+    if context.dry_run {
+      println!(
+        "dry_run - version bump: {} -> {}",
+        package.version.value, release.next_version
+      );
+    } else {
+      context
+        .package_manager
+        .apply_version(&package.path, &release.next_version, &context.metadata)
+        .await?;
+    }
+
+    for update in &release.dependencies {
+      if context.dry_run {
+        println!(
+          "dry_run - dependecy version bump: {} {} -> {}",
+          update.dependency.name, update.dependency.version, update.next_version
+        );
+      } else {
+        context
+          .package_manager
+          .apply_dependency_version(
+            &package.path,
+            update.dependency,
+            &update.next_version,
+            &context.metadata,
+          )
+          .await?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Lets the package manager sync its lockfile with the next versions of the plan
+  async fn update_plan_lockfile<T, V>(
+    context: &ExecutableContext<T, V>,
+    plan: &ReleasePlan<'_, V>,
+  ) -> anyhow::Result<()>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    Self::update_lockfile(
+      context,
+      &plan
+        .packages
+        .iter()
+        .map(|release| (release.package.name.as_str(), release.next_version.clone()))
+        .collect(),
+    )
+    .await
+  }
+
+  /// Lets the package manager sync its lockfile once every version is applied
+  pub(crate) async fn update_lockfile<T, V>(
+    context: &ExecutableContext<T, V>,
+    versions: &HashMap<&str, String>,
+  ) -> anyhow::Result<()>
+  where
+    T: PackageManager + Send + Sync,
+    V: VersionEditor + Send + Sync + 'static,
+    T::Metadata: Send + Sync,
+  {
+    if versions.is_empty() {
+      return Ok(());
+    }
+
+    if context.dry_run {
+      println!("dry_run - update lockfile: {:?}", versions);
+
+      return Ok(());
+    }
+
+    context
+      .package_manager
+      .update_lockfile(
+        context.root_dir.join(T::default_path()),
+        versions,
+        &context.metadata,
+      )
+      .await
+      .context("Failed updating the lockfile")
+  }
 }
 
 #[async_trait]
@@ -31,114 +166,171 @@ where
     context: &ExecutableContext<T, V>,
     plugins: Arc<PluginManager>,
   ) -> anyhow::Result<()> {
+    if self.snapshot.is_some() && self.output == OutputFormat::Json {
+      anyhow::bail!(
+        "--snapshot can't be combined with --output json, snapshots have no release plan"
+      );
+    }
+
     plugins.pre_command("version", &context.as_plugin())?;
 
-    let package_graph = context.packages.as_package_graph();
-    let (changeset_paths, bump) = context.changesets.consume::<V>(&package_graph).await?;
+    let mut pre_release = context.changesets.load_pre_release().await?;
+
+    let package_graph = context.package_graph();
+    let (changeset_paths, bump) = context
+      .changesets
+      .consume_pending::<V>(&package_graph, pre_release.as_ref())
+      .await?;
 
     if bump.is_empty() {
-      println!(
-        "Sorry but no changesets found in {:?}",
-        context.changesets.directory
-      );
+      match self.output {
+        // an empty plan keeps the output parsable for scripts
+        OutputFormat::Json => println!(
+          "{}",
+          serde_json::to_string_pretty(
+            &ReleasePlan::new(&package_graph, &bump)?
+              .report(&context.changesets.config.changelog, pre_release.as_ref())
+          )?
+        ),
+        OutputFormat::Text => println!(
+          "Sorry but no changesets found in {:?}",
+          context.changesets.directory
+        ),
+      }
 
       return Ok(());
     }
 
-    let mut updated = HashMap::new();
+    let plan = match &mut pre_release {
+      Some(pre_release) => {
+        let (_, pre_release_bump) = context
+          .changesets
+          .consume::<V>(&package_graph, Some(pre_release))
+          .await?;
 
-    for package in package_graph.update_order() {
-      if let Some(update) = bump.package(&package.name).version() {
-        let next_version = update
-          .apply(&package.version.value)
-          .with_context(|| format!("Failed updating package {}", package.name))?;
+        let plan = ReleasePlan::pre_release(&package_graph, &bump, pre_release, &pre_release_bump)?;
 
-        updated.insert(package.name.as_str(), next_version.clone());
+        for release in &plan.packages {
+          pre_release
+            .initial_versions
+            .entry(release.package.name.clone())
+            .or_insert_with(|| release.package.version.value.clone());
 
-        if context.dry_run {
-          println!(
-            "dry_run - version bump: {} -> {}",
-            package.version.value, next_version
-          );
-        } else {
-          context
-            .package_manager
-            .apply_version(&package.path, &next_version, &context.metadata)
-            .await?;
-        }
-
-        for (name, version, updated_version) in package
-          .dependencies
-          .iter()
-          .filter(|(name, _)| updated.contains_key(name.as_str()))
-          .filter(|(name, version)| !V::r#match(version, &updated[name.as_str()]))
-          .map(|(name, version)| (name, version, &updated[name.as_str()]))
-        {
-          if context.dry_run {
-            println!(
-              "dry_run - dependecy version bump: {} {} -> {}",
-              name,
-              version,
-              V::mask(version, updated_version)
-            );
-          } else {
-            context
-              .package_manager
-              .apply_dependency_version(
-                &package.path,
-                name,
-                V::mask(version, updated_version),
-                &context.metadata,
-              )
-              .await?;
+          for update in &release.dependencies {
+            pre_release
+              .initial_requirements
+              .entry(release.package.name.clone())
+              .or_default()
+              .entry(update.dependency.name.clone())
+              .or_insert_with(|| update.dependency.version.clone());
           }
         }
 
-        if let Some(root_path) = package.path.parent() {
-          let changelog_path = {
-            let mut root_path = root_path.to_path_buf();
-            root_path.push("CHANGELOG.md");
-            root_path
-          };
-
-          Changelog::update_changelog(
-            &changelog_path,
-            next_version.into(),
-            &bump.package(&package.name),
-            context.dry_run,
-          )
-          .await
-          .with_context(|| {
-            format!(
-              "Could not update the changelog for {} at {:?}",
-              package.name, changelog_path
-            )
-          })?;
-        }
+        plan
       }
+      None => ReleasePlan::new(&package_graph, &bump)?,
+    };
+
+    if let Some(snapshot) = &self.snapshot {
+      let snapshot = format!("{}-{}", snapshot, Utc::now().format("%Y%m%d%H%M%S"));
+
+      // changesets, changelogs and the pre-release state are left untouched
+      let plan = plan
+        .with_snapshot(&package_graph, &snapshot)?
+        .with_keep_build_metadata(context.changesets.config.keep_build_metadata)?;
+
+      for release in &plan.packages {
+        Self::apply_release(context, release).await?;
+      }
+
+      Self::update_plan_lockfile(context, &plan).await?;
+
+      self.run_build(context).await?;
+
+      plugins.post_command("version", &context.as_plugin())?;
+
+      return Ok(());
     }
 
-    if !context.dry_run && !self.no_build {
-      context
-        .package_manager
-        .run_build(
-          &context.root_dir,
-          self.build_args.clone(),
-          &context.metadata,
+    let plan = plan.with_keep_build_metadata(context.changesets.config.keep_build_metadata)?;
+
+    if self.output == OutputFormat::Json {
+      println!(
+        "{}",
+        serde_json::to_string_pretty(
+          &plan.report(&context.changesets.config.changelog, pre_release.as_ref())
+        )?
+      );
+
+      // the json report replaces the dry run output
+      if context.dry_run {
+        plugins.post_command("version", &context.as_plugin())?;
+
+        return Ok(());
+      }
+    }
+
+    for release in &plan.packages {
+      let package = release.package;
+
+      Self::apply_release(context, release).await?;
+
+      if let Some(root_path) = package.path.parent() {
+        let changelog_path = {
+          let mut root_path = root_path.to_path_buf();
+          root_path.push(&context.changesets.config.changelog);
+          root_path
+        };
+
+        Changelog::update_changelog(
+          &changelog_path,
+          release.next_version.clone().into(),
+          &bump.package(&package.name),
+          context.dry_run,
         )
-        .await?;
+        .await
+        .with_context(|| {
+          format!(
+            "Could not update the changelog for {} at {:?}",
+            package.name, changelog_path
+          )
+        })?;
+      }
     }
 
-    for changeset_path in changeset_paths {
+    Self::update_plan_lockfile(context, &plan).await?;
+
+    if let Some(mut pre_release) = pre_release {
+      for changeset_path in &changeset_paths {
+        pre_release.insert(changeset_path);
+      }
+
       if context.dry_run {
-        println!("dry_run - delete: {:?}", changeset_path);
+        println!(
+          "dry_run - record pre-release changesets: {:?}",
+          changeset_paths
+        );
       } else {
-        fs::remove_file(&changeset_path)
-          .await
-          .with_context(|| format!("Unable to remove the changeset at {:?}", changeset_path))?;
+        pre_release
+          .save(context.changesets.pre_release_path())
+          .await?;
+      }
+    } else {
+      for changeset_path in changeset_paths {
+        if context.dry_run {
+          println!("dry_run - delete: {:?}", changeset_path);
+        } else {
+          fs::remove_file(&changeset_path)
+            .await
+            .with_context(|| format!("Unable to remove the changeset at {:?}", changeset_path))?;
+        }
       }
     }
 
+    // the build runs once the changesets are consumed so a failing build doesn't leave bumped
+    // versions with changesets that would bump them again
+    self.run_build(context).await?;
+
     plugins.post_command("version", &context.as_plugin())?;
 
     Ok(())
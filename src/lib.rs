@@ -75,7 +75,7 @@ where
   for plugin in &opts.plugins {
     unsafe {
       plugin_manager
-        .load(&plugin, &context.as_plugin())
+        .load(plugin, &context.as_plugin())
         .with_context(|| format!("Could not load plugin at path {}", plugin))?;
     }
   }